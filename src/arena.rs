@@ -0,0 +1,556 @@
+use bevy::color::palettes::basic::{RED, YELLOW};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use crate::grid::*;
+use crate::highscore::name_entry_closed;
+use crate::level::Level;
+
+// enough for a circle pushed out of a polygon corner
+const ARENA_PUSH_ITERATIONS: i32 = 4;
+
+pub struct ArenaPlugin;
+
+impl Plugin for ArenaPlugin {
+    fn build (&self, app: &mut App) {
+        app.add_systems(Startup, bound_start);
+        app.add_systems(Update, (arena_shrink_toggle.run_if(name_entry_closed), arena_shrink_update, draw_bound).chain());
+    }
+}
+
+/// Outline of the playing area. Polygon points go in any order and the polygon may be concave.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ArenaShape {
+    Circle { center: Vec2, radius: f32 },
+    Rect { center: Vec2, half_size: Vec2 },
+    RoundedRect { center: Vec2, half_size: Vec2, corner_radius: f32 },
+    Polygon { points: Vec<Vec2> },
+}
+
+/// Food and snakes are kept inside the shape of the bound.
+#[derive(Component)]
+pub struct Bound {
+    pub shape: ArenaShape,
+}
+
+/// One step of the shrinking arena: the warning ring is shown for wait_seconds,
+/// then the bound shrinks to the scale of the start shape in shrink_seconds.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ArenaShrinkPhase {
+    pub wait_seconds: f32,
+    pub shrink_seconds: f32,
+    pub scale: f32,
+}
+
+/// Battle royale mode. Bound shape becomes the safe zone which shrinks by phases,
+/// snakes are still kept inside start_shape but lose size outside the safe zone.
+#[derive(Component)]
+pub struct ArenaShrink {
+    pub start_shape: ArenaShape,
+    pub phases: Vec<ArenaShrinkPhase>,
+    pub phase_index: usize,
+    // seconds since the current phase started
+    pub phase_time: f32,
+    // size lost every second outside the safe zone
+    pub size_loss_per_second: f32,
+}
+
+pub fn arena_shrink_new(start_shape: ArenaShape) -> ArenaShrink {
+    ArenaShrink {
+        start_shape,
+        phases: vec![
+            ArenaShrinkPhase { wait_seconds: 20.0, shrink_seconds: 10.0, scale: 0.75 },
+            ArenaShrinkPhase { wait_seconds: 15.0, shrink_seconds: 10.0, scale: 0.5 },
+            ArenaShrinkPhase { wait_seconds: 15.0, shrink_seconds: 10.0, scale: 0.25 },
+        ],
+        phase_index: 0,
+        phase_time: 0.0,
+        size_loss_per_second: 1.0,
+    }
+}
+
+fn arena_shrink_phase_start_scale(shrink: &ArenaShrink, phase_index: usize) -> f32 {
+    if phase_index == 0 { 1.0 } else { shrink.phases[phase_index - 1].scale }
+}
+
+/// Moves the shrink time forward and returns the scale of the safe zone.
+pub fn arena_shrink_advance(shrink: &mut ArenaShrink, time_delta_seconds: f32) -> f32 {
+    shrink.phase_time += time_delta_seconds;
+    while let Some(phase) = shrink.phases.get(shrink.phase_index) {
+        if shrink.phase_time < phase.wait_seconds + phase.shrink_seconds {
+            let start_scale = arena_shrink_phase_start_scale(shrink, shrink.phase_index);
+            let fraction = ((shrink.phase_time - phase.wait_seconds) / phase.shrink_seconds.max(f32::EPSILON)).clamp(0.0, 1.0);
+            return start_scale + (phase.scale - start_scale) * fraction;
+        }
+        shrink.phase_time -= phase.wait_seconds + phase.shrink_seconds;
+        shrink.phase_index += 1;
+    }
+    arena_shrink_phase_start_scale(shrink, shrink.phases.len())
+}
+
+/// Scale of the safe zone after the current phase, None when all phases are finished.
+pub fn arena_shrink_warning_scale(shrink: &ArenaShrink) -> Option<f32> {
+    shrink.phases.get(shrink.phase_index).map(|phase| phase.scale)
+}
+
+/// Snakes are blocked by the start shape in battle royale mode and by the bound shape otherwise.
+pub fn arena_wall<'a>(bound: &'a Bound, shrink: Option<&'a ArenaShrink>) -> &'a ArenaShape {
+    match shrink {
+        Some(shrink) => &shrink.start_shape,
+        None => &bound.shape,
+    }
+}
+
+/// Shape moved by delta.
+pub fn arena_translated(shape: &ArenaShape, delta: Vec2) -> ArenaShape {
+    match shape {
+        ArenaShape::Circle { center, radius } => ArenaShape::Circle { center: *center + delta, radius: *radius },
+        ArenaShape::Rect { center, half_size } => ArenaShape::Rect { center: *center + delta, half_size: *half_size },
+        ArenaShape::RoundedRect { center, half_size, corner_radius } => ArenaShape::RoundedRect {
+            center: *center + delta,
+            half_size: *half_size,
+            corner_radius: *corner_radius,
+        },
+        ArenaShape::Polygon { points } => ArenaShape::Polygon { points: points.iter().map(|point| *point + delta).collect() },
+    }
+}
+
+/// Shape scaled around the center of its bounding box.
+pub fn arena_scaled(shape: &ArenaShape, scale: f32) -> ArenaShape {
+    match shape {
+        ArenaShape::Circle { center, radius } => ArenaShape::Circle { center: *center, radius: radius * scale },
+        ArenaShape::Rect { center, half_size } => ArenaShape::Rect { center: *center, half_size: *half_size * scale },
+        ArenaShape::RoundedRect { center, half_size, corner_radius } => ArenaShape::RoundedRect {
+            center: *center,
+            half_size: *half_size * scale,
+            corner_radius: corner_radius * scale,
+        },
+        ArenaShape::Polygon { points } => {
+            let (min, max) = arena_bounding_box(shape);
+            let center = (min + max) / 2.0;
+            ArenaShape::Polygon { points: points.iter().map(|point| center + (*point - center) * scale).collect() }
+        }
+    }
+}
+
+/// Levels with shrink phases start in battle royale mode.
+/// The open world has no bound, so nothing is pushed back or reflected there.
+fn bound_start(mut commands: Commands, level: Res<Level>) {
+    if level.open_world.is_some() {
+        return;
+    }
+    let mut bound = commands.spawn(Bound { shape: level.arena.clone() });
+    if !level.shrink.is_empty() {
+        let mut shrink = arena_shrink_new(level.arena.clone());
+        shrink.phases = level.shrink.clone();
+        bound.insert(shrink);
+    }
+}
+
+/// Digit2 starts and stops the battle royale mode.
+fn arena_shrink_toggle(
+    mut commands: Commands,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut bound_query: Query<(Entity, &mut Bound, Option<&ArenaShrink>)>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Digit2) {
+        return;
+    }
+    for (entity, mut bound, shrink) in &mut bound_query {
+        match shrink {
+            Some(shrink) => {
+                bound.shape = shrink.start_shape.clone();
+                commands.entity(entity).remove::<ArenaShrink>();
+            }
+            None => {
+                commands.entity(entity).insert(arena_shrink_new(bound.shape.clone()));
+            }
+        }
+    }
+}
+
+fn arena_shrink_update(mut bound_query: Query<(&mut Bound, &mut ArenaShrink)>, time: Res<Time>) {
+    for (mut bound, mut shrink) in &mut bound_query {
+        let scale = arena_shrink_advance(&mut shrink, time.delta_seconds());
+        bound.shape = arena_scaled(&shrink.start_shape, scale);
+    }
+}
+
+/// Safe zone and warning ring of the battle royale mode are always drawn, the usual bound only with visual diagnostics.
+fn draw_bound(
+    mut gizmos: Gizmos,
+    bound_query: Query<(&Bound, Option<&ArenaShrink>)>,
+    query: Query<&GridVisualDiagnostic>
+) {
+    for (bound, shrink) in &bound_query {
+        match shrink {
+            Some(shrink) => {
+                arena_draw(&mut gizmos, &shrink.start_shape, Color::WHITE);
+                arena_draw(&mut gizmos, &bound.shape, RED.into());
+                if let Some(scale) = arena_shrink_warning_scale(shrink) {
+                    arena_draw(&mut gizmos, &arena_scaled(&shrink.start_shape, scale), YELLOW.into());
+                }
+            }
+            None => {
+                if grid_draw_visual_diagnostics_info(&query) {
+                    arena_draw(&mut gizmos, &bound.shape, RED.into());
+                }
+            }
+        }
+    }
+}
+
+pub fn arena_draw<Config: GizmoConfigGroup>(gizmos: &mut Gizmos<Config>, shape: &ArenaShape, color: Color) {
+    match shape {
+        ArenaShape::Circle { center, radius } => {
+            gizmos.circle_2d(*center, *radius, color);
+        }
+        ArenaShape::Rect { center, half_size } => {
+            gizmos.rect_2d(*center, 0.0, *half_size * 2.0, color);
+        }
+        ArenaShape::RoundedRect { center, half_size, corner_radius } => {
+            gizmos.rounded_rect_2d(*center, 0.0, *half_size * 2.0, color).corner_radius(*corner_radius);
+        }
+        ArenaShape::Polygon { points } => {
+            gizmos.linestrip_2d(points.iter().chain(points.first()).copied(), color);
+        }
+    }
+}
+
+pub fn segment_closest_point(a: Vec2, b: Vec2, p: Vec2) -> Vec2 {
+    let ab = b - a;
+    let length_squared = ab.length_squared();
+    if length_squared == 0.0 {
+        return a;
+    }
+    a + ab * ((p - a).dot(ab) / length_squared).clamp(0.0, 1.0)
+}
+
+/// Even-odd rule, works for concave polygons.
+pub fn polygon_contains(points: &[Vec2], p: Vec2) -> bool {
+    let mut inside = false;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+/// Positive for counterclockwise points.
+fn polygon_signed_area(points: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        area += a.perp_dot(b);
+    }
+    area / 2.0
+}
+
+/// Closest point of the polygon outline and outward normal there.
+/// Normal looks from the closest point to p, so it is correct near corners too.
+pub fn polygon_closest(points: &[Vec2], p: Vec2) -> (Vec2, Vec2) {
+    let winding = polygon_signed_area(points).signum();
+    let mut closest = p;
+    let mut edge_normal = Vec2::X;
+    let mut min_distance = f32::MAX;
+    for i in 0..points.len() {
+        let a = points[i];
+        let b = points[(i + 1) % points.len()];
+        let edge_closest = segment_closest_point(a, b, p);
+        let distance = edge_closest.distance(p);
+        if distance < min_distance {
+            min_distance = distance;
+            closest = edge_closest;
+            edge_normal = Vec2::new(b.y - a.y, a.x - b.x).normalize_or_zero() * winding;
+        }
+    }
+    let outward = if polygon_contains(points, p) { closest - p } else { p - closest };
+    (closest, outward.try_normalize().unwrap_or(edge_normal))
+}
+
+/// Closest point of the rectangle outline and outward normal there.
+/// Rounded rectangle is the rectangle shrunk by corner_radius and expanded back as a circle.
+fn rect_closest(center: Vec2, half_size: Vec2, corner_radius: f32, p: Vec2) -> (Vec2, Vec2) {
+    let inner_half_size = (half_size - Vec2::splat(corner_radius)).max(Vec2::ZERO);
+    let local = p - center;
+    let clamped = local.clamp(-inner_half_size, inner_half_size);
+    if clamped != local {
+        // outside the inner rectangle, closest point is on the side or on the corner arc
+        let normal = (local - clamped).normalize();
+        return (center + clamped + normal * corner_radius, normal);
+    }
+    // inside the inner rectangle, the nearest side is the closest
+    let to_side = inner_half_size - local.abs();
+    let normal = if to_side.x < to_side.y {
+        Vec2::new(local.x.signum(), 0.0)
+    } else {
+        Vec2::new(0.0, local.y.signum())
+    };
+    let on_side = if normal.x != 0.0 {
+        Vec2::new(normal.x * inner_half_size.x, local.y)
+    } else {
+        Vec2::new(local.x, normal.y * inner_half_size.y)
+    };
+    (center + on_side + normal * corner_radius, normal)
+}
+
+/// Closest point of the shape outline and outward normal of the outline there.
+fn arena_closest(shape: &ArenaShape, p: Vec2) -> (Vec2, Vec2) {
+    match shape {
+        ArenaShape::Circle { center, radius } => {
+            let normal = (p - *center).try_normalize().unwrap_or(Vec2::X);
+            (*center + normal * *radius, normal)
+        }
+        ArenaShape::Rect { center, half_size } => rect_closest(*center, *half_size, 0.0, p),
+        ArenaShape::RoundedRect { center, half_size, corner_radius } => rect_closest(*center, *half_size, *corner_radius, p),
+        ArenaShape::Polygon { points } => polygon_closest(points, p),
+    }
+}
+
+pub fn arena_contains(shape: &ArenaShape, p: Vec2) -> bool {
+    match shape {
+        ArenaShape::Polygon { points } => polygon_contains(points, p),
+        _ => arena_signed_distance(shape, p) <= 0.0,
+    }
+}
+
+/// Outward normal of the outline at the point closest to p.
+pub fn arena_normal(shape: &ArenaShape, p: Vec2) -> Vec2 {
+    arena_closest(shape, p).1
+}
+
+/// Distance to the outline, negative inside the shape.
+pub fn arena_signed_distance(shape: &ArenaShape, p: Vec2) -> f32 {
+    let (closest, normal) = arena_closest(shape, p);
+    match shape {
+        ArenaShape::Polygon { points } => {
+            let distance = closest.distance(p);
+            if polygon_contains(points, p) { -distance } else { distance }
+        }
+        _ => (p - closest).dot(normal),
+    }
+}
+
+/// Position of a circle moved inside the shape if it crosses the outline.
+/// Concave corners of a polygon may need several pushes.
+pub fn arena_push_inside(shape: &ArenaShape, pos: Vec2, radius: f32) -> Vec2 {
+    let mut pos = pos;
+    for _ in 0..ARENA_PUSH_ITERATIONS {
+        if arena_signed_distance(shape, pos) <= -radius {
+            break;
+        }
+        let (closest, normal) = arena_closest(shape, pos);
+        pos = closest - normal * radius;
+    }
+    pos
+}
+
+/// Velocity mirrored about the wall with the given unit normal.
+pub fn arena_reflect(velocity: Vec2, normal: Vec2) -> Vec2 {
+    velocity - 2.0 * velocity.dot(normal) * normal
+}
+
+/// Circle touching the outline is pushed back inside and its velocity is reflected if it moves outward.
+/// Returns new position and velocity.
+pub fn arena_bounce(shape: &ArenaShape, pos: Vec2, radius: f32, velocity: Vec2) -> (Vec2, Vec2) {
+    if arena_signed_distance(shape, pos) < -radius {
+        return (pos, velocity);
+    }
+    let normal = arena_normal(shape, pos);
+    let velocity = if velocity.dot(normal) > 0.0 { arena_reflect(velocity, normal) } else { velocity };
+    (arena_push_inside(shape, pos, radius), velocity)
+}
+
+pub fn arena_area(shape: &ArenaShape) -> f32 {
+    match shape {
+        ArenaShape::Circle { radius, .. } => PI * radius * radius,
+        ArenaShape::Rect { half_size, .. } => half_size.x * half_size.y * 4.0,
+        ArenaShape::RoundedRect { half_size, corner_radius, .. } => half_size.x * half_size.y * 4.0 - (4.0 - PI) * corner_radius * corner_radius,
+        ArenaShape::Polygon { points } => polygon_signed_area(points).abs(),
+    }
+}
+
+/// Minimum and maximum corners of the box around the shape.
+pub fn arena_bounding_box(shape: &ArenaShape) -> (Vec2, Vec2) {
+    match shape {
+        ArenaShape::Circle { center, radius } => (*center - Vec2::splat(*radius), *center + Vec2::splat(*radius)),
+        ArenaShape::Rect { center, half_size } => (*center - *half_size, *center + *half_size),
+        ArenaShape::RoundedRect { center, half_size, .. } => (*center - *half_size, *center + *half_size),
+        ArenaShape::Polygon { points } => points.iter().fold(
+            (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+            |(min, max), point| (min.min(*point), max.max(*point)),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec2_eq(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 0.001, "{a} != {b}");
+    }
+
+    fn concave_polygon() -> ArenaShape {
+        // U shape opened to the top
+        ArenaShape::Polygon { points: vec![
+            Vec2::new(0.0, 0.0), Vec2::new(30.0, 0.0), Vec2::new(30.0, 30.0), Vec2::new(20.0, 30.0),
+            Vec2::new(20.0, 10.0), Vec2::new(10.0, 10.0), Vec2::new(10.0, 30.0), Vec2::new(0.0, 30.0),
+        ]}
+    }
+
+    #[test]
+    fn circle_off_center() {
+        let shape = ArenaShape::Circle { center: Vec2::new(100.0, 0.0), radius: 50.0 };
+        assert!(arena_contains(&shape, Vec2::new(140.0, 0.0)));
+        assert!(!arena_contains(&shape, Vec2::new(40.0, 0.0)));
+        assert_vec2_eq(arena_closest(&shape, Vec2::new(100.0, 10.0)).0, Vec2::new(100.0, 50.0));
+        assert_vec2_eq(arena_normal(&shape, Vec2::new(0.0, 0.0)), Vec2::new(-1.0, 0.0));
+        assert!((arena_signed_distance(&shape, Vec2::new(100.0, 20.0)) + 30.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn rect_inside_and_outside() {
+        let shape = ArenaShape::Rect { center: Vec2::ZERO, half_size: Vec2::new(20.0, 10.0) };
+        assert!(arena_contains(&shape, Vec2::new(19.0, 9.0)));
+        assert!(!arena_contains(&shape, Vec2::new(21.0, 0.0)));
+        // inside, nearest side is the top one
+        assert_vec2_eq(arena_closest(&shape, Vec2::new(0.0, 8.0)).0, Vec2::new(0.0, 10.0));
+        assert_vec2_eq(arena_normal(&shape, Vec2::new(0.0, 8.0)), Vec2::Y);
+        // outside the corner
+        assert_vec2_eq(arena_closest(&shape, Vec2::new(30.0, 20.0)).0, Vec2::new(20.0, 10.0));
+        assert!((arena_signed_distance(&shape, Vec2::new(25.0, 0.0)) - 5.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn rounded_rect_corner_is_arc() {
+        let shape = ArenaShape::RoundedRect { center: Vec2::ZERO, half_size: Vec2::new(20.0, 20.0), corner_radius: 10.0 };
+        // corner of the box is outside of the rounded corner
+        assert!(!arena_contains(&shape, Vec2::new(19.0, 19.0)));
+        assert!(arena_contains(&shape, Vec2::new(19.0, 0.0)));
+        let normal = arena_normal(&shape, Vec2::new(30.0, 30.0));
+        assert_vec2_eq(normal, Vec2::new(1.0, 1.0).normalize());
+        assert_vec2_eq(arena_closest(&shape, Vec2::new(30.0, 30.0)).0, Vec2::splat(10.0) + normal * 10.0);
+    }
+
+    #[test]
+    fn concave_polygon_notch_is_outside() {
+        let shape = concave_polygon();
+        assert!(arena_contains(&shape, Vec2::new(5.0, 20.0)));
+        assert!(arena_contains(&shape, Vec2::new(15.0, 5.0)));
+        assert!(!arena_contains(&shape, Vec2::new(15.0, 20.0)));
+        assert!(arena_signed_distance(&shape, Vec2::new(15.0, 20.0)) > 0.0);
+    }
+
+    #[test]
+    fn polygon_normal_points_out_for_both_windings() {
+        let counterclockwise = vec![Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0)];
+        let mut clockwise = counterclockwise.clone();
+        clockwise.reverse();
+        for points in [counterclockwise, clockwise] {
+            let shape = ArenaShape::Polygon { points };
+            assert_vec2_eq(arena_normal(&shape, Vec2::new(5.0, 9.0)), Vec2::Y);
+            assert_vec2_eq(arena_normal(&shape, Vec2::new(5.0, 10.0)), Vec2::Y);
+        }
+    }
+
+    #[test]
+    fn push_inside_keeps_circle_within_shape() {
+        let shapes = [
+            ArenaShape::Circle { center: Vec2::new(10.0, 10.0), radius: 50.0 },
+            ArenaShape::Rect { center: Vec2::ZERO, half_size: Vec2::new(40.0, 30.0) },
+            ArenaShape::RoundedRect { center: Vec2::ZERO, half_size: Vec2::new(40.0, 30.0), corner_radius: 10.0 },
+            concave_polygon(),
+        ];
+        for shape in shapes.iter() {
+            for pos in [Vec2::new(100.0, 5.0), Vec2::new(-60.0, -70.0), Vec2::new(15.0, 20.0)] {
+                let pushed = arena_push_inside(shape, pos, 2.0);
+                assert!(arena_signed_distance(shape, pushed) <= -1.999, "{shape:?} {pos}");
+            }
+        }
+        let shape = &shapes[0];
+        assert_eq!(arena_push_inside(shape, Vec2::new(20.0, 10.0), 2.0), Vec2::new(20.0, 10.0));
+    }
+
+    #[test]
+    fn reflect_about_normal() {
+        assert_vec2_eq(arena_reflect(Vec2::new(1.0, -1.0), Vec2::Y), Vec2::new(1.0, 1.0));
+        assert_vec2_eq(arena_reflect(Vec2::new(3.0, 0.0), Vec2::NEG_X), Vec2::new(-3.0, 0.0));
+        let normal = Vec2::new(1.0, 1.0).normalize();
+        assert_vec2_eq(arena_reflect(Vec2::new(1.0, 0.0), normal), Vec2::new(0.0, -1.0));
+        // speed does not change
+        assert!((arena_reflect(Vec2::new(2.0, 5.0), normal).length() - Vec2::new(2.0, 5.0).length()).abs() < 0.001);
+    }
+
+    #[test]
+    fn bounce_off_center_circle() {
+        let shape = ArenaShape::Circle { center: Vec2::new(300.0, -200.0), radius: 100.0 };
+        let (pos, velocity) = arena_bounce(&shape, Vec2::new(405.0, -200.0), 10.0, Vec2::new(5.0, 2.0));
+        assert_vec2_eq(pos, Vec2::new(390.0, -200.0));
+        assert_vec2_eq(velocity, Vec2::new(-5.0, 2.0));
+    }
+
+    #[test]
+    fn bounce_keeps_inward_velocity() {
+        let shape = ArenaShape::Rect { center: Vec2::ZERO, half_size: Vec2::new(50.0, 50.0) };
+        let (pos, velocity) = arena_bounce(&shape, Vec2::new(0.0, 45.0), 10.0, Vec2::new(1.0, -3.0));
+        assert_vec2_eq(pos, Vec2::new(0.0, 40.0));
+        assert_vec2_eq(velocity, Vec2::new(1.0, -3.0));
+    }
+
+    #[test]
+    fn no_bounce_far_from_wall() {
+        let shape = ArenaShape::Rect { center: Vec2::new(100.0, 0.0), half_size: Vec2::new(50.0, 50.0) };
+        let (pos, velocity) = arena_bounce(&shape, Vec2::new(100.0, 0.0), 10.0, Vec2::new(0.0, 7.0));
+        assert_eq!((pos, velocity), (Vec2::new(100.0, 0.0), Vec2::new(0.0, 7.0)));
+    }
+
+    fn shrink_for_test() -> ArenaShrink {
+        let mut shrink = arena_shrink_new(ArenaShape::Circle { center: Vec2::new(50.0, 0.0), radius: 100.0 });
+        shrink.phases = vec![
+            ArenaShrinkPhase { wait_seconds: 10.0, shrink_seconds: 10.0, scale: 0.5 },
+            ArenaShrinkPhase { wait_seconds: 5.0, shrink_seconds: 0.0, scale: 0.2 },
+        ];
+        shrink
+    }
+
+    #[test]
+    fn shrink_waits_then_shrinks() {
+        let mut shrink = shrink_for_test();
+        assert_eq!(arena_shrink_advance(&mut shrink, 5.0), 1.0);
+        assert_eq!(arena_shrink_warning_scale(&shrink), Some(0.5));
+        assert!((arena_shrink_advance(&mut shrink, 10.0) - 0.75).abs() < 0.001);
+        assert!((arena_shrink_advance(&mut shrink, 6.0) - 0.5).abs() < 0.001);
+        assert_eq!(shrink.phase_index, 1);
+        assert_eq!(arena_shrink_warning_scale(&shrink), Some(0.2));
+    }
+
+    #[test]
+    fn shrink_stops_after_last_phase() {
+        let mut shrink = shrink_for_test();
+        assert!((arena_shrink_advance(&mut shrink, 100.0) - 0.2).abs() < 0.001);
+        assert_eq!(arena_shrink_warning_scale(&shrink), None);
+        assert!((arena_shrink_advance(&mut shrink, 1.0) - 0.2).abs() < 0.001);
+    }
+
+    #[test]
+    fn scaled_shapes_keep_center() {
+        let circle = arena_scaled(&ArenaShape::Circle { center: Vec2::new(50.0, 0.0), radius: 100.0 }, 0.5);
+        assert_eq!(circle, ArenaShape::Circle { center: Vec2::new(50.0, 0.0), radius: 50.0 });
+        let polygon = arena_scaled(&concave_polygon(), 0.5);
+        assert_eq!(arena_bounding_box(&polygon), (Vec2::splat(7.5), Vec2::splat(22.5)));
+    }
+
+    #[test]
+    fn area_of_shapes() {
+        assert!((arena_area(&ArenaShape::Rect { center: Vec2::ONE, half_size: Vec2::new(2.0, 3.0) }) - 24.0).abs() < 0.001);
+        assert!((arena_area(&concave_polygon()) - 700.0).abs() < 0.001);
+        let (min, max) = arena_bounding_box(&concave_polygon());
+        assert_eq!((min, max), (Vec2::ZERO, Vec2::new(30.0, 30.0)));
+    }
+}
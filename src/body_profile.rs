@@ -0,0 +1,114 @@
+/// One point of the snake body profile.
+#[derive(Clone)]
+pub struct BodyProfileKeyframe {
+    // normalized body position: 0.0 is the head, 1.0 is the end of the tail
+    pub position: f32,
+    // node radius at this position, used for sprite scale, node spacing and collisions
+    pub radius: f32,
+    // sprite used for nodes starting from this keyframe position
+    pub sprite: &'static str,
+    // diameter in sprite image pixels which matches the node circle
+    pub sprite_size: f32,
+}
+
+/// Body profile describes how node radius and sprite change from head to tail.
+/// Keyframes must be sorted by position.
+#[derive(Clone)]
+pub struct BodyProfile {
+    pub keyframes: Vec<BodyProfileKeyframe>,
+}
+
+/// Thick neck and thin tail.
+pub fn body_profile_default() -> BodyProfile {
+    BodyProfile {
+        keyframes: vec![
+            BodyProfileKeyframe { position: 0.0, radius: 12.0, sprite: "SpinePart.png", sprite_size: 100.0 },
+            BodyProfileKeyframe { position: 0.2, radius: 14.0, sprite: "SpinePart.png", sprite_size: 100.0 },
+            BodyProfileKeyframe { position: 0.8, radius: 8.0, sprite: "SpinePart.png", sprite_size: 100.0 },
+            BodyProfileKeyframe { position: 1.0, radius: 5.0, sprite: "SpineStart.png", sprite_size: 60.0 },
+        ],
+    }
+}
+
+/// Index of the last keyframe with position before or equal to the given position.
+fn body_profile_keyframe_index(profile: &BodyProfile, position: f32) -> usize {
+    let mut result = 0;
+    for (i, keyframe) in profile.keyframes.iter().enumerate() {
+        if keyframe.position <= position {
+            result = i;
+        }
+    }
+    result
+}
+
+/// Radius linearly interpolated between the two keyframes around the given normalized position.
+pub fn body_profile_radius(profile: &BodyProfile, position: f32) -> f32 {
+    let position = position.clamp(0.0, 1.0);
+    let index = body_profile_keyframe_index(profile, position);
+    let current = &profile.keyframes[index];
+    match profile.keyframes.get(index + 1) {
+        None => current.radius,
+        Some(next) => {
+            let fraction = (position - current.position) / (next.position - current.position);
+            current.radius + (next.radius - current.radius) * fraction
+        }
+    }
+}
+
+/// Keyframe which defines sprite on the given normalized position.
+pub fn body_profile_keyframe(profile: &BodyProfile, position: f32) -> &BodyProfileKeyframe {
+    let index = body_profile_keyframe_index(profile, position.clamp(0.0, 1.0));
+    &profile.keyframes[index]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_float_eq(a: f32, b: f32) {
+        let delta_max = 0.001;
+        let c = f32::abs(a - b);
+        assert!(c < delta_max);
+    }
+
+    fn profile_for_test() -> BodyProfile {
+        BodyProfile {
+            keyframes: vec![
+                BodyProfileKeyframe { position: 0.0, radius: 10.0, sprite: "A.png", sprite_size: 100.0 },
+                BodyProfileKeyframe { position: 0.5, radius: 20.0, sprite: "B.png", sprite_size: 100.0 },
+                BodyProfileKeyframe { position: 1.0, radius: 0.0, sprite: "C.png", sprite_size: 100.0 },
+            ],
+        }
+    }
+
+    #[test]
+    fn radius_on_keyframes() {
+        let profile = profile_for_test();
+        assert_float_eq(body_profile_radius(&profile, 0.0), 10.0);
+        assert_float_eq(body_profile_radius(&profile, 0.5), 20.0);
+        assert_float_eq(body_profile_radius(&profile, 1.0), 0.0);
+    }
+
+    #[test]
+    fn radius_between_keyframes() {
+        let profile = profile_for_test();
+        assert_float_eq(body_profile_radius(&profile, 0.25), 15.0);
+        assert_float_eq(body_profile_radius(&profile, 0.75), 10.0);
+    }
+
+    #[test]
+    fn radius_out_of_range_is_clamped() {
+        let profile = profile_for_test();
+        assert_float_eq(body_profile_radius(&profile, -1.0), 10.0);
+        assert_float_eq(body_profile_radius(&profile, 2.0), 0.0);
+    }
+
+    #[test]
+    fn sprite_from_previous_keyframe() {
+        let profile = profile_for_test();
+        assert_eq!(body_profile_keyframe(&profile, 0.0).sprite, "A.png");
+        assert_eq!(body_profile_keyframe(&profile, 0.49).sprite, "A.png");
+        assert_eq!(body_profile_keyframe(&profile, 0.5).sprite, "B.png");
+        assert_eq!(body_profile_keyframe(&profile, 1.0).sprite, "C.png");
+    }
+}
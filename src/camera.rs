@@ -0,0 +1,254 @@
+use bevy::prelude::*;
+use bevy::render::camera::{ClearColorConfig, Viewport};
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+use crate::minimap::MINIMAP_RENDER_LAYER;
+use crate::player::Player;
+use crate::snake_extension::snake_update;
+use crate::snake_model::SnakeModel;
+
+// space around the snakes framed by the shared camera
+const SPLIT_SCREEN_MARGIN: f32 = 150.0;
+// the shared camera is used again only when the snakes fit into this part of the max zoom,
+// so the screen does not switch back and forth at the limit
+const SPLIT_SCREEN_JOIN_FRACTION: f32 = 0.8;
+
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build (&self, app: &mut App) {
+        app.insert_resource(SplitScreen { shared: false });
+        app.add_systems(Update, camera_follow_update.after(snake_update));
+    }
+}
+
+/// Every player has a camera with own viewport. While all snakes are close together the camera of the first player
+/// shows all of them on the whole window and the other cameras are off.
+#[derive(Resource)]
+pub struct SplitScreen {
+    pub shared: bool,
+}
+
+/// Camera which follows the snake head of the player and zooms out as the snake grows.
+#[derive(Component)]
+pub struct CameraFollow {
+    pub player: usize,
+    // distance ahead of the head in its heading direction
+    pub look_ahead: f32,
+    // the camera does not move while the target is this close to the center
+    pub dead_zone: f32,
+    // how quickly the camera catches up with the target, bigger is faster
+    pub damping: f32,
+    // snake size which is shown without zoom
+    pub zoom_base_size: f32,
+    // projection scale added for every size unit above zoom_base_size
+    pub zoom_per_size: f32,
+    pub zoom_max: f32,
+    pub zoom_damping: f32,
+}
+
+pub fn camera_follow_new(player: usize) -> CameraFollow {
+    CameraFollow {
+        player,
+        look_ahead: 80.0,
+        dead_zone: 40.0,
+        damping: 3.0,
+        zoom_base_size: 5.0,
+        zoom_per_size: 0.03,
+        zoom_max: 3.0,
+        zoom_damping: 1.0,
+    }
+}
+
+pub fn camera_spawn(commands: &mut Commands, player: Player) {
+    let camera = Camera {
+        // cameras with different order do not fight over the window
+        order: player.index as isize,
+        // the first camera has already cleared the whole window
+        clear_color: if player.index > 0 { ClearColorConfig::None } else { ClearColorConfig::default() },
+        ..default()
+    };
+    let mut camera_entity = commands.spawn((Camera2dBundle { camera, ..default() }, camera_follow_new(player.index)));
+    if player.index == 0 {
+        camera_entity.insert(RenderLayers::from_layers(&[0, MINIMAP_RENDER_LAYER]));
+    }
+}
+
+/// Point the camera tries to center on.
+pub fn camera_target(follow: &CameraFollow, head_pos: Vec2, head_direction_angle: f32) -> Vec2 {
+    head_pos + Vec2::from_angle(head_direction_angle) * follow.look_ahead
+}
+
+/// Frame rate independent part of the remaining way covered in time_delta_seconds.
+pub fn camera_damping_fraction(damping: f32, time_delta_seconds: f32) -> f32 {
+    1.0 - (-damping * time_delta_seconds).exp()
+}
+
+/// New camera position. The camera moves only to keep the target inside the dead zone circle.
+pub fn camera_follow_step(follow: &CameraFollow, camera_pos: Vec2, target: Vec2, time_delta_seconds: f32) -> Vec2 {
+    let offset = target - camera_pos;
+    let distance = offset.length();
+    if distance <= follow.dead_zone {
+        return camera_pos;
+    }
+    let wanted = target - offset / distance * follow.dead_zone;
+    camera_pos + (wanted - camera_pos) * camera_damping_fraction(follow.damping, time_delta_seconds)
+}
+
+/// Projection scale for the snake size, 1 is no zoom.
+pub fn camera_zoom_for_size(follow: &CameraFollow, size: f32) -> f32 {
+    (1.0 + (size - follow.zoom_base_size) * follow.zoom_per_size).clamp(1.0, follow.zoom_max)
+}
+
+/// Projection scale which shows all positions with the margin around them in the view.
+pub fn camera_frame_zoom(positions: &[Vec2], view_size: Vec2, margin: f32) -> f32 {
+    let min = positions.iter().fold(Vec2::splat(f32::MAX), |min, pos| min.min(*pos));
+    let max = positions.iter().fold(Vec2::splat(f32::MIN), |max, pos| max.max(*pos));
+    let framed = max - min + Vec2::splat(margin * 2.0);
+    f32::max(framed.x / view_size.x, framed.y / view_size.y)
+}
+
+pub fn split_screen_is_shared(frame_zoom: f32, zoom_max: f32, was_shared: bool) -> bool {
+    if was_shared { frame_zoom <= zoom_max } else { frame_zoom <= zoom_max * SPLIT_SCREEN_JOIN_FRACTION }
+}
+
+/// Position and size of every viewport in window pixels. Two players split the window into left and right halves,
+/// three and four players into quarters.
+pub fn split_screen_viewports(count: usize, window_size: UVec2) -> Vec<(UVec2, UVec2)> {
+    match count {
+        0 | 1 => vec![(UVec2::ZERO, window_size)],
+        2 => {
+            let size = UVec2::new(window_size.x / 2, window_size.y);
+            vec![(UVec2::ZERO, size), (UVec2::new(size.x, 0), size)]
+        }
+        _ => {
+            let size = window_size / 2;
+            (0..count as u32).map(|i| (UVec2::new(i % 2, i / 2) * size, size)).collect()
+        }
+    }
+}
+
+pub fn camera_follow_update(
+    mut camera_query: Query<(&CameraFollow, &mut Camera, &mut Transform, &mut OrthographicProjection)>,
+    snake_query: Query<(&SnakeModel, &Player)>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    mut split_screen: ResMut<SplitScreen>,
+    time: Res<Time>,
+) {
+    let Ok(window) = window_query.get_single() else { return; };
+    let heads: Vec<Vec2> = snake_query.iter().map(|(snake, _)| snake.head_pos).collect();
+    let camera_count = camera_query.iter().count();
+    let zoom_max = camera_query.iter().next().map_or(1.0, |(follow, ..)| follow.zoom_max);
+    let frame_zoom = camera_frame_zoom(&heads, window.size(), SPLIT_SCREEN_MARGIN);
+    split_screen.shared = camera_count > 1 && heads.len() > 1 && split_screen_is_shared(frame_zoom, zoom_max, split_screen.shared);
+    let viewports = split_screen_viewports(camera_count, window.physical_size());
+
+    for (follow, mut camera, mut transform, mut projection) in &mut camera_query {
+        let shared_camera = split_screen.shared && follow.player == 0;
+        camera.is_active = !split_screen.shared || shared_camera;
+        camera.viewport = if camera_count == 1 || shared_camera { None } else {
+            viewports.get(follow.player).map(|(physical_position, physical_size)| Viewport {
+                physical_position: *physical_position,
+                physical_size: *physical_size,
+                ..default()
+            })
+        };
+
+        let (target, zoom) = if shared_camera {
+            let center = heads.iter().sum::<Vec2>() / heads.len() as f32;
+            let max_size = snake_query.iter().map(|(snake, _)| snake.size).fold(0.0, f32::max);
+            (center, f32::max(frame_zoom, camera_zoom_for_size(follow, max_size)))
+        }
+        else {
+            let Some((snake, _)) = snake_query.iter().find(|(_, player)| player.index == follow.player) else { continue; };
+            (camera_target(follow, snake.head_pos, snake.head_direction_angle), camera_zoom_for_size(follow, snake.size))
+        };
+        let pos = camera_follow_step(follow, transform.translation.truncate(), target, time.delta_seconds());
+        transform.translation = pos.extend(transform.translation.z);
+        projection.scale += (zoom - projection.scale) * camera_damping_fraction(follow.zoom_damping, time.delta_seconds());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_stays_while_target_is_in_dead_zone() {
+        let follow = camera_follow_new(0);
+        let pos = Vec2::new(10.0, 0.0);
+        assert_eq!(camera_follow_step(&follow, pos, pos + Vec2::new(0.0, follow.dead_zone), 0.1), pos);
+    }
+
+    #[test]
+    fn camera_moves_toward_target_without_overshoot() {
+        let follow = camera_follow_new(0);
+        let target = Vec2::new(1000.0, 0.0);
+        let mut pos = Vec2::ZERO;
+        for _ in 0..600 {
+            let next = camera_follow_step(&follow, pos, target, 1.0 / 60.0);
+            assert!(next.x >= pos.x && next.x <= target.x - follow.dead_zone);
+            pos = next;
+        }
+        // the camera ends up at the dead zone border
+        assert!((pos.x - (1000.0 - follow.dead_zone)).abs() < 1.0);
+        assert!(pos.y.abs() < 0.001);
+    }
+
+    #[test]
+    fn damping_does_not_depend_on_frame_rate() {
+        let once = camera_damping_fraction(3.0, 0.2);
+        let twice = 1.0 - (1.0 - camera_damping_fraction(3.0, 0.1)).powi(2);
+        assert!((once - twice).abs() < 0.0001);
+    }
+
+    #[test]
+    fn target_is_ahead_of_head() {
+        let follow = camera_follow_new(0);
+        let target = camera_target(&follow, Vec2::new(5.0, 5.0), std::f32::consts::FRAC_PI_2);
+        assert!((target - Vec2::new(5.0, 5.0 + follow.look_ahead)).length() < 0.001);
+    }
+
+    #[test]
+    fn bigger_snake_zooms_out_up_to_max() {
+        let follow = camera_follow_new(0);
+        assert_eq!(camera_zoom_for_size(&follow, 1.0), 1.0);
+        assert!(camera_zoom_for_size(&follow, 20.0) > camera_zoom_for_size(&follow, 10.0));
+        assert_eq!(camera_zoom_for_size(&follow, 10000.0), follow.zoom_max);
+    }
+
+    #[test]
+    fn viewports_cover_window_without_overlap() {
+        let window = UVec2::new(1280, 720);
+        for count in 1..=4 {
+            let viewports = split_screen_viewports(count, window);
+            assert_eq!(viewports.len(), count);
+            for (pos, size) in viewports.iter() {
+                assert!(pos.x + size.x <= window.x && pos.y + size.y <= window.y);
+            }
+            for (i, (a, a_size)) in viewports.iter().enumerate() {
+                for (b, _) in viewports[i + 1..].iter() {
+                    let inside = b.x >= a.x && b.x < a.x + a_size.x && b.y >= a.y && b.y < a.y + a_size.y;
+                    assert!(!inside, "{count} viewports overlap");
+                }
+            }
+        }
+        assert_eq!(split_screen_viewports(2, window)[1], (UVec2::new(640, 0), UVec2::new(640, 720)));
+        assert_eq!(split_screen_viewports(4, window)[3], (UVec2::new(640, 360), UVec2::new(640, 360)));
+    }
+
+    #[test]
+    fn frame_zoom_fits_positions_and_margin() {
+        let zoom = camera_frame_zoom(&[Vec2::new(-300.0, 0.0), Vec2::new(300.0, 100.0)], Vec2::new(800.0, 600.0), 100.0);
+        assert!((zoom - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn shared_screen_has_hysteresis() {
+        assert!(split_screen_is_shared(2.0, 3.0, false));
+        assert!(!split_screen_is_shared(2.7, 3.0, false));
+        assert!(split_screen_is_shared(2.7, 3.0, true));
+        assert!(!split_screen_is_shared(3.1, 3.0, true));
+    }
+}
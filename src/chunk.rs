@@ -0,0 +1,185 @@
+use bevy::color::palettes::css::DARK_CYAN;
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+use crate::food::{food_spawn, Food};
+use crate::food_kind::*;
+use crate::grid::*;
+use crate::level::Level;
+use crate::snake_model::SnakeModel;
+
+pub struct ChunkPlugin;
+
+impl Plugin for ChunkPlugin {
+    fn build (&self, app: &mut App) {
+        app.add_systems(Startup, chunk_start);
+        app.add_systems(Update, (chunk_update, draw_chunks).chain());
+    }
+}
+
+/// Open world settings of a level. The world has no bound, food is generated in square chunks around the snakes.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct OpenWorld {
+    // the same seed generates the same food in the same chunks
+    pub seed: u64,
+    pub chunk_size: f32,
+    // chunks up to this many chunks away from a snake head are loaded
+    pub load_distance: i32,
+}
+
+/// Loaded chunks. A chunk is unloaded one chunk further than it is loaded, so moving along a chunk border
+/// does not load and unload it every frame.
+#[derive(Component)]
+pub struct ChunkWorld {
+    settings: OpenWorld,
+    // food density like FoodSpawner.target_density
+    target_density: f32,
+    loaded: HashSet<IVec2>,
+}
+
+fn chunk_start(mut commands: Commands, level: Res<Level>) {
+    if let Some(open_world) = &level.open_world {
+        commands.spawn(ChunkWorld { settings: open_world.clone(), target_density: level.food.target_density, loaded: HashSet::new() });
+    }
+}
+
+pub fn chunk_of(pos: Vec2, chunk_size: f32) -> IVec2 {
+    (pos / chunk_size).floor().as_ivec2()
+}
+
+/// Seed of a single chunk mixed from the world seed and chunk coordinates.
+pub fn chunk_seed(seed: u64, chunk: IVec2) -> u64 {
+    let x = chunk.x as u32 as u64;
+    let y = chunk.y as u32 as u64;
+    seed ^ x.wrapping_mul(0x9E37_79B9_7F4A_7C15) ^ y.wrapping_mul(0xC2B2_AE3D_27D4_EB4F).rotate_left(32)
+}
+
+/// Chunks within distance chunks of any position, in both axes.
+pub fn chunks_near(positions: &[Vec2], chunk_size: f32, distance: i32) -> Vec<IVec2> {
+    let mut chunks = Vec::new();
+    for pos in positions {
+        let center = chunk_of(*pos, chunk_size);
+        for x in -distance..=distance {
+            for y in -distance..=distance {
+                let chunk = center + IVec2::new(x, y);
+                if !chunks.contains(&chunk) {
+                    chunks.push(chunk);
+                }
+            }
+        }
+    }
+    chunks
+}
+
+/// Kind index and position of every food in the chunk. The result depends only on the arguments.
+pub fn chunk_food(settings: &OpenWorld, target_density: f32, food_kinds: &FoodKinds, chunk: IVec2) -> Vec<(usize, Vec2)> {
+    let mut rng = StdRng::seed_from_u64(chunk_seed(settings.seed, chunk));
+    let count = (target_density * settings.chunk_size * settings.chunk_size / (100.0 * 100.0)).round() as usize;
+    let min = chunk.as_vec2() * settings.chunk_size;
+    (0..count)
+        .map(|_| {
+            let kind_index = food_kind_random(food_kinds, &mut rng);
+            let pos = min + Vec2::new(rng.gen_range(0.0..settings.chunk_size), rng.gen_range(0.0..settings.chunk_size));
+            (kind_index, pos)
+        })
+        .collect()
+}
+
+/// Loads chunks near snake heads and unloads far ones. All food is unloaded by the chunk it is in now, so pellets,
+/// food of dead snakes and food which moved away from its chunk are handled too. Eaten food comes back when
+/// the chunk is loaded again.
+fn chunk_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut world_query: Query<&mut ChunkWorld>,
+    food_kinds_query: Query<&FoodKinds>,
+    food_query: Query<(Entity, &Food)>,
+    snake_query: Query<&SnakeModel>,
+) {
+    let Ok(mut world) = world_query.get_single_mut() else { return; };
+    let Ok(food_kinds) = food_kinds_query.get_single() else { return; };
+    let heads: Vec<Vec2> = snake_query.iter().map(|snake| snake.head_pos).collect();
+    if heads.is_empty() {
+        return;
+    }
+    let chunk_size = world.settings.chunk_size;
+    let load_distance = world.settings.load_distance;
+
+    let keep: HashSet<IVec2> = chunks_near(&heads, chunk_size, load_distance + 1).into_iter().collect();
+    world.loaded.retain(|chunk| keep.contains(chunk));
+    for (entity, food) in &food_query {
+        if !keep.contains(&chunk_of(food.pos, chunk_size)) {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    for chunk in chunks_near(&heads, chunk_size, load_distance) {
+        if !world.loaded.insert(chunk) {
+            continue;
+        }
+        for (kind_index, pos) in chunk_food(&world.settings, world.target_density, food_kinds, chunk) {
+            food_spawn(&mut commands, &asset_server, kind_index, &food_kinds.kinds[kind_index], pos);
+        }
+    }
+}
+
+fn draw_chunks(mut gizmos: Gizmos, world_query: Query<&ChunkWorld>, grid_query: Query<&GridVisualDiagnostic>) {
+    if !grid_draw_visual_diagnostics_info(&grid_query) {
+        return;
+    }
+    for world in &world_query {
+        let size = world.settings.chunk_size;
+        for chunk in world.loaded.iter() {
+            let center = (chunk.as_vec2() + Vec2::splat(0.5)) * size;
+            gizmos.rect_2d(center, 0.0, Vec2::splat(size), DARK_CYAN);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> OpenWorld {
+        OpenWorld { seed: 7, chunk_size: 400.0, load_distance: 1 }
+    }
+
+    #[test]
+    fn position_to_chunk() {
+        assert_eq!(chunk_of(Vec2::new(10.0, 399.0), 400.0), IVec2::new(0, 0));
+        assert_eq!(chunk_of(Vec2::new(-1.0, 400.0), 400.0), IVec2::new(-1, 1));
+    }
+
+    #[test]
+    fn chunks_near_overlapping_heads_are_unique() {
+        let chunks = chunks_near(&[Vec2::new(10.0, 10.0), Vec2::new(420.0, 10.0)], 400.0, 1);
+        assert_eq!(chunks.len(), 12);
+        assert!(chunks.contains(&IVec2::new(-1, -1)));
+        assert!(chunks.contains(&IVec2::new(2, 1)));
+    }
+
+    #[test]
+    fn chunk_food_is_deterministic_and_inside_chunk() {
+        let food_kinds = food_kinds_new(food_kinds_default()).unwrap();
+        let chunk = IVec2::new(-3, 2);
+        let food = chunk_food(&settings(), 0.5, &food_kinds, chunk);
+        assert_eq!(food.len(), 8);
+        assert_eq!(food, chunk_food(&settings(), 0.5, &food_kinds, chunk));
+        for (_, pos) in food.iter() {
+            assert_eq!(chunk_of(*pos, 400.0), chunk);
+        }
+    }
+
+    #[test]
+    fn neighbour_chunks_and_seeds_differ() {
+        let food_kinds = food_kinds_new(food_kinds_default()).unwrap();
+        let a = chunk_food(&settings(), 0.5, &food_kinds, IVec2::new(0, 1));
+        let b = chunk_food(&settings(), 0.5, &food_kinds, IVec2::new(1, 0));
+        let c = chunk_food(&OpenWorld { seed: 8, ..settings() }, 0.5, &food_kinds, IVec2::new(0, 1));
+        assert_ne!(a.iter().map(|(_, pos)| *pos - Vec2::new(0.0, 400.0)).collect::<Vec<_>>(), b.iter().map(|(_, pos)| *pos - Vec2::new(400.0, 0.0)).collect::<Vec<_>>());
+        assert_ne!(a, c);
+    }
+}
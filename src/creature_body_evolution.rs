@@ -1,6 +1,10 @@
 use bevy::prelude::*;
 use bevy::{sprite::SpriteBundle};
-use crate::snake_model::{SnakeModel, SnakeSpineNode, SnakeSpineNodeType as SnakeSpineNodeType};
+use crate::body_profile::*;
+use crate::snake_model::{snake_last_node_index, snake_node_body_position, snake_node_radius, SnakeModel, SnakeSpineNode};
+
+// diameter in SpineHead.png pixels which matches the head circle
+const SNAKE_HEAD_SPRITE_SIZE: f32 = 240.0;
 
 /// All creature visual movable parts will have this component to query their transformations.  
 #[derive(Component)]
@@ -34,10 +38,11 @@ pub fn spine_from_size(commands: &mut Commands,  asset_server: &Res<AssetServer>
     //         CreatureBodyVisualElement
     //     )).id();
 
+    let head_scale = snake.head_radius * 2.0 / SNAKE_HEAD_SPRITE_SIZE;
     let head_entity = commands.spawn((
         SpriteBundle {
             texture: asset_server.load("SpineHead.png"),
-            transform: Transform::from_xyz(1000.0, 0.0, 0.0).with_scale(Vec3::new(0.1,0.1, 0.1)),
+            transform: Transform::from_xyz(1000.0, 0.0, 0.0).with_scale(Vec3::new(head_scale, head_scale, head_scale)),
             ..default()
         },
         CreatureBodyVisualElement
@@ -47,31 +52,55 @@ pub fn spine_from_size(commands: &mut Commands,  asset_server: &Res<AssetServer>
     list.push(SnakeSpineNode {
         distance_from_head: 0.0,
         node_type: head_entity,
+        radius: snake.head_radius,
+        sprite: "SpineHead.png",
+        pos: snake.head_pos,
+        direction: snake.head_direction_angle,
     });
 
-    for _ in 0..100 {
+    for i in 1..=snake_last_node_index(snake) {
+        list.push(spine_node_spawn(commands, asset_server, snake, i));
+    }
+    
+    return list;
+}
+
+fn spine_node_spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, snake: &SnakeModel, node_index: usize) -> SnakeSpineNode {
+    let radius = snake_node_radius(snake, node_index);
+    let keyframe = body_profile_keyframe(&snake.body_profile, snake_node_body_position(snake, node_index));
+    let scale = radius * 2.0 / keyframe.sprite_size;
     let node_entity = commands.spawn((
         SpriteBundle {
-            texture: asset_server.load("SpinePart.png"),
-            transform: Transform::from_xyz(1000.0, 0.0, 0.0).with_scale(Vec3::new(0.2, 0.2, 0.0)),
+            texture: asset_server.load(keyframe.sprite),
+            transform: Transform::from_xyz(1000.0, 0.0, 0.0).with_scale(Vec3::new(scale, scale, 0.0)),
             ..default()
         },
         CreatureBodyVisualElement
     )).id();
 
-    list.push(SnakeSpineNode {
-        distance_from_head: 50.0,
+    SnakeSpineNode {
+        distance_from_head: 0.0,
         node_type: node_entity,
-    });
+        radius,
+        sprite: keyframe.sprite,
+        pos: snake.head_pos,
+        direction: snake.head_direction_angle,
+    }
+}
+
+/// Spawns new tail nodes when the snake size grows over the number of spawned nodes.
+pub fn spine_grow(commands: &mut Commands, asset_server: &Res<AssetServer>, snake: &mut SnakeModel) {
+    while snake.body.len() <= snake_last_node_index(snake) {
+        let node = spine_node_spawn(commands, asset_server, snake, snake.body.len());
+        snake.body.push(node);
     }
-    
-    return list;
 }
 
-fn node_radius(node: SnakeSpineNodeType) -> f32 {
-    match node {
-        SnakeSpineNodeType::Small => { 10.0 }
-        SnakeSpineNodeType::Medium => { 15.0 }
-        SnakeSpineNodeType::Big => { 20.0 }
+/// Despawns tail node sprites which are not needed after the snake size decreased.
+pub fn spine_shrink(commands: &mut Commands, snake: &mut SnakeModel) {
+    while snake.body.len() > snake_last_node_index(snake) + 1 {
+        if let Some(node) = snake.body.pop() {
+            commands.entity(node.node_type).despawn();
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,346 @@
+use bevy::color::palettes::css::{GREEN, SKY_BLUE, YELLOW};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::arena::*;
+use crate::camera::CameraFollow;
+use crate::food_spawn::FoodSpawner;
+use crate::grid::*;
+use crate::highscore::name_entry_closed;
+use crate::level::*;
+use crate::obstacle::*;
+
+pub const EDITOR_SAVE_PATH: &str = "assets/levels/edited.ron";
+// how far from an item the cursor may be to pick it
+const EDITOR_PICK_DISTANCE: f32 = 10.0;
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build (&self, app: &mut App) {
+        app.add_systems(Startup, editor_start);
+        app.add_systems(Update, (editor_toggle.run_if(name_entry_closed), editor_update, draw_editor).chain());
+    }
+}
+
+/// What a left click on empty space places.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorTool {
+    CircleObstacle,
+    WallObstacle,
+    FoodRegion,
+    SpawnPoint,
+}
+
+/// Level item by its index in the level lists.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EditorItem {
+    Obstacle(usize),
+    FoodRegion(usize),
+    SpawnPoint(usize),
+}
+
+/// Editor mode pauses the game. Edits change the Level resource, F5 saves it.
+#[derive(Component)]
+pub struct LevelEditor {
+    enabled: bool,
+    tool: EditorTool,
+    selected: Option<EditorItem>,
+    // cursor position snapped to the grid when dragging the selected item
+    drag_from: Option<Vec2>,
+}
+
+fn editor_start(mut commands: Commands) {
+    commands.spawn(LevelEditor { enabled: false, tool: EditorTool::CircleObstacle, selected: None, drag_from: None });
+}
+
+fn editor_toggle(
+    mut editor_query: Query<&mut LevelEditor>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+    for mut editor in &mut editor_query {
+        editor.enabled = !editor.enabled;
+        editor.selected = None;
+        editor.drag_from = None;
+        if editor.enabled { time.pause(); } else { time.unpause(); }
+    }
+}
+
+/// Position rounded to the nearest grid node.
+pub fn editor_snap(pos: Vec2, cell_size: f32) -> Vec2 {
+    if cell_size <= 0.0 {
+        return pos;
+    }
+    (pos / cell_size).round() * cell_size
+}
+
+/// Item under the cursor. Spawn points are picked first, then obstacles, then food regions.
+pub fn editor_item_at(level: &Level, pos: Vec2) -> Option<EditorItem> {
+    if let Some(i) = level.spawn_points.iter().position(|point| point.pos.distance(pos) <= EDITOR_PICK_DISTANCE) {
+        return Some(EditorItem::SpawnPoint(i));
+    }
+    if let Some(i) = level.obstacles.iter().position(|obstacle| obstacle_signed_distance(&obstacle.shape, pos) <= EDITOR_PICK_DISTANCE) {
+        return Some(EditorItem::Obstacle(i));
+    }
+    level.food.regions.iter().position(|region| arena_contains(region, pos)).map(EditorItem::FoodRegion)
+}
+
+/// Places a new item of the tool kind and returns it.
+pub fn editor_item_add(level: &mut Level, tool: EditorTool, pos: Vec2, cell_size: f32) -> EditorItem {
+    match tool {
+        EditorTool::CircleObstacle => {
+            level.obstacles.push(ObstacleDefinition {
+                shape: ObstacleShape::Circle { center: pos, radius: cell_size / 2.0 },
+                contact: ObstacleContact::Block,
+                sprite: None,
+            });
+            EditorItem::Obstacle(level.obstacles.len() - 1)
+        }
+        EditorTool::WallObstacle => {
+            level.obstacles.push(ObstacleDefinition {
+                shape: ObstacleShape::Segment { a: pos - Vec2::new(cell_size, 0.0), b: pos + Vec2::new(cell_size, 0.0), thickness: 16.0 },
+                contact: ObstacleContact::Block,
+                sprite: None,
+            });
+            EditorItem::Obstacle(level.obstacles.len() - 1)
+        }
+        EditorTool::FoodRegion => {
+            level.food.regions.push(ArenaShape::Circle { center: pos, radius: cell_size * 2.0 });
+            EditorItem::FoodRegion(level.food.regions.len() - 1)
+        }
+        EditorTool::SpawnPoint => {
+            level.spawn_points.push(SnakeSpawnPoint { pos, heading_degrees: 90.0 });
+            EditorItem::SpawnPoint(level.spawn_points.len() - 1)
+        }
+    }
+}
+
+pub fn editor_item_move(level: &mut Level, item: EditorItem, delta: Vec2) {
+    match item {
+        EditorItem::Obstacle(i) => {
+            let obstacle = &mut level.obstacles[i];
+            obstacle.shape = obstacle_translated(&obstacle.shape, delta);
+        }
+        EditorItem::FoodRegion(i) => {
+            level.food.regions[i] = arena_translated(&level.food.regions[i], delta);
+        }
+        EditorItem::SpawnPoint(i) => {
+            level.spawn_points[i].pos += delta;
+        }
+    }
+}
+
+/// Removes the item. The last spawn point is kept because a level needs one.
+pub fn editor_item_delete(level: &mut Level, item: EditorItem) -> bool {
+    match item {
+        EditorItem::Obstacle(i) => { level.obstacles.remove(i); }
+        EditorItem::FoodRegion(i) => { level.food.regions.remove(i); }
+        EditorItem::SpawnPoint(i) => {
+            if level.spawn_points.len() <= 1 {
+                return false;
+            }
+            level.spawn_points.remove(i);
+        }
+    }
+    true
+}
+
+fn editor_tool_from_keys(keyboard_input: &ButtonInput<KeyCode>) -> Option<EditorTool> {
+    if keyboard_input.just_pressed(KeyCode::KeyO) { Some(EditorTool::CircleObstacle) }
+    else if keyboard_input.just_pressed(KeyCode::KeyW) { Some(EditorTool::WallObstacle) }
+    else if keyboard_input.just_pressed(KeyCode::KeyR) { Some(EditorTool::FoodRegion) }
+    else if keyboard_input.just_pressed(KeyCode::KeyP) { Some(EditorTool::SpawnPoint) }
+    else { None }
+}
+
+fn editor_cursor_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform), With<CameraFollow>>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    // with split screen the cursor is in the viewport of one of the cameras
+    camera_query.iter().filter(|(camera, _)| camera.is_active).find_map(|(camera, camera_transform)| {
+        let viewport = camera.logical_viewport_rect()?;
+        if !viewport.contains(cursor) {
+            return None;
+        }
+        camera.viewport_to_world_2d(camera_transform, cursor - viewport.min)
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+fn editor_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut level: ResMut<Level>,
+    mut editor_query: Query<&mut LevelEditor>,
+    grid_query: Query<&GridVisualDiagnostic>,
+    obstacle_query: Query<Entity, With<Obstacle>>,
+    mut spawner_query: Query<&mut FoodSpawner>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform), With<CameraFollow>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+) {
+    let Ok(mut editor) = editor_query.get_single_mut() else { return; };
+    if !editor.enabled {
+        return;
+    }
+    if let Some(tool) = editor_tool_from_keys(&keyboard_input) {
+        editor.tool = tool;
+    }
+    if keyboard_input.just_pressed(KeyCode::F5) {
+        match level_save(&level, EDITOR_SAVE_PATH) {
+            Ok(()) => println!("Level saved: {EDITOR_SAVE_PATH}"),
+            Err(error) => println!("Level is not saved: {error}"),
+        }
+    }
+
+    let cell_size = grid_cell_size(&grid_query);
+    let cursor = editor_cursor_position(&window_query, &camera_query).map(|pos| editor_snap(pos, cell_size));
+    let mut changed = false;
+
+    if let Some(EditorItem::Obstacle(i)) = editor.selected {
+        if keyboard_input.just_pressed(KeyCode::KeyK) {
+            let obstacle = &mut level.obstacles[i];
+            obstacle.contact = match obstacle.contact {
+                ObstacleContact::Block => ObstacleContact::Kill,
+                ObstacleContact::Kill => ObstacleContact::Block,
+            };
+            changed = true;
+        }
+    }
+
+    let delete_selected = keyboard_input.just_pressed(KeyCode::Delete) || keyboard_input.just_pressed(KeyCode::Backspace);
+    if let Some(item) = editor.selected.filter(|_| delete_selected) {
+        changed |= editor_item_delete(&mut level, item);
+        editor.selected = None;
+    }
+
+    if let Some(pos) = cursor {
+        if mouse_input.just_pressed(MouseButton::Right) {
+            if let Some(item) = editor_item_at(&level, pos) {
+                changed |= editor_item_delete(&mut level, item);
+                editor.selected = None;
+            }
+        }
+        if mouse_input.just_pressed(MouseButton::Left) {
+            editor.selected = match editor_item_at(&level, pos) {
+                Some(item) => Some(item),
+                None => {
+                    changed = true;
+                    Some(editor_item_add(&mut level, editor.tool, pos, cell_size))
+                }
+            };
+            editor.drag_from = Some(pos);
+        }
+        if mouse_input.pressed(MouseButton::Left) {
+            if let (Some(item), Some(from)) = (editor.selected, editor.drag_from) {
+                if pos != from {
+                    editor_item_move(&mut level, item, pos - from);
+                    editor.drag_from = Some(pos);
+                    changed = true;
+                }
+            }
+        }
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        editor.drag_from = None;
+    }
+
+    if changed {
+        obstacles_rebuild(&mut commands, &asset_server, &obstacle_query, &level.obstacles);
+        for mut spawner in &mut spawner_query {
+            spawner.regions = level.food.regions.clone();
+        }
+    }
+}
+
+fn draw_editor(
+    mut gizmos: Gizmos,
+    level: Res<Level>,
+    editor_query: Query<&LevelEditor>,
+    grid_query: Query<&GridVisualDiagnostic>,
+    camera_query: Query<&GlobalTransform, With<CameraFollow>>,
+) {
+    let Ok(editor) = editor_query.get_single() else { return; };
+    if !editor.enabled {
+        return;
+    }
+    grid_draw_lines(&mut gizmos, grid_cell_size(&grid_query), grid_camera_center(&camera_query));
+    for region in level.food.regions.iter() {
+        arena_draw(&mut gizmos, region, GREEN.into());
+    }
+    for point in level.spawn_points.iter() {
+        gizmos.circle_2d(point.pos, EDITOR_PICK_DISTANCE, SKY_BLUE);
+        let heading = Vec2::from_angle(point.heading_degrees.to_radians());
+        gizmos.line_2d(point.pos, point.pos + heading * EDITOR_PICK_DISTANCE * 3.0, SKY_BLUE);
+    }
+    match editor.selected {
+        Some(EditorItem::Obstacle(i)) => {
+            let (center, radius) = obstacle_bounding_circle(&level.obstacles[i].shape);
+            gizmos.circle_2d(center, radius + 4.0, YELLOW);
+        }
+        Some(EditorItem::FoodRegion(i)) => {
+            arena_draw(&mut gizmos, &level.food.regions[i], YELLOW.into());
+        }
+        Some(EditorItem::SpawnPoint(i)) => {
+            gizmos.circle_2d(level.spawn_points[i].pos, EDITOR_PICK_DISTANCE + 4.0, YELLOW);
+        }
+        None => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_to_nearest_grid_node() {
+        assert_eq!(editor_snap(Vec2::new(24.0, -26.0), 50.0), Vec2::new(0.0, -50.0));
+        assert_eq!(editor_snap(Vec2::new(76.0, 101.0), 50.0), Vec2::new(100.0, 100.0));
+        assert_eq!(editor_snap(Vec2::new(3.3, 4.4), 0.0), Vec2::new(3.3, 4.4));
+    }
+
+    #[test]
+    fn spawn_point_is_picked_before_obstacle() {
+        let mut level = level_default();
+        level.obstacles = vec![];
+        let obstacle = editor_item_add(&mut level, EditorTool::CircleObstacle, Vec2::ZERO, 50.0);
+        assert_eq!(obstacle, EditorItem::Obstacle(0));
+        assert_eq!(editor_item_at(&level, Vec2::ZERO), Some(EditorItem::SpawnPoint(0)));
+        assert_eq!(editor_item_at(&level, Vec2::new(20.0, 0.0)), Some(EditorItem::Obstacle(0)));
+        assert_eq!(editor_item_at(&level, Vec2::new(100.0, 0.0)), None);
+    }
+
+    #[test]
+    fn moved_items_keep_their_size() {
+        let mut level = level_default();
+        let region = editor_item_add(&mut level, EditorTool::FoodRegion, Vec2::ZERO, 50.0);
+        let wall = editor_item_add(&mut level, EditorTool::WallObstacle, Vec2::ZERO, 50.0);
+        editor_item_move(&mut level, region, Vec2::new(50.0, 0.0));
+        editor_item_move(&mut level, wall, Vec2::new(0.0, 100.0));
+        assert_eq!(level.food.regions[0], ArenaShape::Circle { center: Vec2::new(50.0, 0.0), radius: 100.0 });
+        let EditorItem::Obstacle(i) = wall else { panic!() };
+        assert_eq!(
+            level.obstacles[i].shape,
+            ObstacleShape::Segment { a: Vec2::new(-50.0, 100.0), b: Vec2::new(50.0, 100.0), thickness: 16.0 }
+        );
+    }
+
+    #[test]
+    fn last_spawn_point_is_not_deleted() {
+        let mut level = level_default();
+        let point = editor_item_add(&mut level, EditorTool::SpawnPoint, Vec2::new(100.0, 0.0), 50.0);
+        assert!(editor_item_delete(&mut level, point));
+        assert!(!editor_item_delete(&mut level, EditorItem::SpawnPoint(0)));
+        assert_eq!(level.spawn_points.len(), 1);
+        let obstacles = level.obstacles.len();
+        assert!(editor_item_delete(&mut level, EditorItem::Obstacle(0)));
+        assert_eq!(level.obstacles.len(), obstacles - 1);
+    }
+}
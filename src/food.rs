@@ -1,114 +1,199 @@
 use bevy::app::{App, Plugin, Startup, Update};
-use bevy::color::palettes::basic::RED;
 use bevy::color::{Color, Srgba};
 use bevy::math::Vec2;
 use bevy::prelude::*;
 use rand::Rng;
+use std::collections::HashMap;
 use std::f32::*;
 use std::f64::consts::PI;
 
-use crate::snake_model::SnakeModel;
+use crate::arena::*;
+use crate::food_kind::*;
+use crate::food_spawn::*;
+use crate::food_steering::*;
+use crate::snake_extension::snake_update;
+use crate::snake_model::{snake_effect_add, snake_magnet_pull, snake_swallow, SnakeModel};
 use crate::grid::*;
+use crate::level::Level;
+use crate::obstacle::*;
+use crate::player::Player;
+use crate::spatial_hash::*;
 use crate::start::*;
 
 pub struct FoodPlugin;
 
 #[derive(Component)]
 pub struct Food {
-    pos: Vec2,
+    pub pos: Vec2,
     direction: f32,
-    radius: f32,
-    color: Srgba,
-}
-#[derive(Component)]
-pub struct Bound {
-    pos: Vec2,
-    radius: f32,
+    pub radius: f32,
+    pub color: Srgba,
+    // size added to the snake which eats this food
+    nutrition: f32,
+    // score added when this food is eaten
+    score: i32,
+    // movement in units per second
+    speed: f32,
+    effect: Option<FoodEffect>,
+    // index in FoodKinds, None for pellets
+    kind: Option<usize>,
+    steering: Option<FoodSteering>,
+    wander_angle: f32,
+    visual: FoodVisual,
+    // seconds since spawn, used by the sprite animation
+    age: f32,
 }
 
+// speed of food without steering in units per second
+const FOOD_DEFAULT_SPEED: f32 = 60.0;
 #[derive(Component)]
-struct Score {
-    score_num: i32
+pub struct Score {
+    pub score_num: i32,
+    // index of the player who gets this score
+    pub player: usize,
 }
 
 impl Plugin for FoodPlugin {
     fn build (&self, app: &mut App) {
         app.add_systems(Startup, food_start);
-        app.add_systems(Startup, score_start);
-        app.add_systems(Startup, bound_start);
-        app.add_systems(Update, food_update);
-        app.add_systems(Update, draw_bound);
+        app.add_systems(Update, (food_spawn_update, spatial_hash_rebuild.after(snake_update), food_update).chain());
     }
 }
 
-fn bound_start(mut commands: Commands) {
-    commands.spawn(Bound{
-        pos: Vec2::new(0.0, 0.0),
-        radius: 500.0,
-    });
+/// Score of the player, it is kept when the snake dies and shown by the HUD.
+pub fn score_spawn(commands: &mut Commands, player: Player) {
+    commands.spawn(Score { score_num: 0, player: player.index });
+}
+/// Food is not spawned here, the spawner fills the bound on the first update.
+fn food_start (mut commands: Commands, level: Res<Level>) {
+    commands.spawn(food_kinds_from_file_or_default(&level.food.kinds_path, &level.food.kinds));
+    let mut spawner = food_spawner_new(level.food.policy.clone());
+    spawner.target_density = level.food.target_density;
+    spawner.spawn_per_second = level.food.spawn_per_second;
+    spawner.regions = level.food.regions.clone();
+    commands.spawn(spawner);
 }
-fn score_start(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let font = asset_server.load("MovistarTextRegular.ttf");
-    let text_style = TextStyle {
-        font: font.clone(),
-        font_size: 50.0,
-        ..default()
-    };
-    let text_justification = JustifyText::Center;
 
+pub fn food_spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, kind_index: usize, kind: &FoodKind, pos: Vec2) {
+    let direction = rand::thread_rng().gen_range(0.0..= consts::PI * 2.0);
     commands.spawn((
-        Text2dBundle {
-            text: Text::from_section("no score", text_style.clone())
-                .with_justify(text_justification),
-            transform: Transform::from_xyz(0.0, 200.0, 0.0),
+        SpriteBundle {
+            texture: asset_server.load(kind.sprite.clone()),
+            sprite: Sprite { color: food_kind_sprite_color(kind), ..default() },
+            // scale is set by food_update when the image is loaded
+            transform: Transform::from_xyz(pos.x, pos.y, 0.0).with_scale(Vec3::ZERO),
             ..default()
         },
-        Score { score_num: 0 }
+        Food {
+            pos,
+            direction,
+            radius: kind.radius,
+            color: new_food_color(),
+            nutrition: kind.nutrition,
+            score: kind.score,
+            speed: FOOD_DEFAULT_SPEED,
+            effect: kind.effect.clone(),
+            kind: Some(kind_index),
+            steering: kind.steering.clone(),
+            wander_angle: direction,
+            visual: kind.visual.clone(),
+            age: 0.0,
+        }
     ));
 }
-fn food_start (mut commands: Commands, asset_server: Res<AssetServer>) {
-    let food_image_size = 100.0;
-    let radius = 10.0;
-    let scale = (radius * 2.0) / food_image_size;
-    for _ in 0..5 {
-        commands.spawn((
-            SpriteBundle {
-                texture: asset_server.load("Food.png"),
-                transform: Transform::from_xyz(120.0, 0.0, 0.0).with_scale(Vec3::new(scale, scale, scale)),
-                ..default()
-            },
-            Food {
-                pos: new_food_position(),
-                direction: new_food_direction(rand::thread_rng().gen_range(0.0..= consts::PI * 2.0) as f32),
-                radius,
-                color: new_food_color(),
-            }
-        ));
-    }
-}
 
-fn draw_bound(
-    mut gizmos: Gizmos,
-    bound_query: Query<&mut Bound>,
-    query: Query<&GridVisualDiagnostic>
+/// Spawns food of random kinds until the bound has the target amount of it. Pellets are not counted.
+#[allow(clippy::too_many_arguments)]
+fn food_spawn_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    food_kinds_query: Query<&FoodKinds>,
+    mut spawner_query: Query<&mut FoodSpawner>,
+    bound_query: Query<&Bound>,
+    food_query: Query<&Food>,
+    snake_query: Query<&SnakeModel>,
+    time: Res<Time>,
 ) {
-    for bound in &bound_query{
-        if grid_draw_visual_diagnostics_info(&query) {
-            gizmos.circle_2d(bound.pos, bound.radius, RED);
+    let food_kinds = food_kinds_query.single();
+    let mut spawner = spawner_query.single_mut();
+    let heads: Vec<Vec2> = snake_query.iter().map(|snake| snake.head_pos).collect();
+    let current_count = food_query.iter().filter(|food| food.kind.is_some()).count();
+    let mut rng = rand::thread_rng();
+    for bound in &bound_query {
+        let target_count = food_target_count(&spawner, arena_area(&bound.shape));
+        for _ in 0..food_spawn_count(&mut spawner, current_count, target_count, time.delta_seconds()) {
+            let kind_index = food_kind_random(food_kinds, &mut rng);
+            let kind = &food_kinds.kinds[kind_index];
+            let pos = food_spawn_position(&mut spawner, &bound.shape, kind.radius, &heads, &mut rng);
+            food_spawn(&mut commands, &asset_server, kind_index, kind, pos);
         }
     }
 }
 
-fn new_food_position() -> Vec2 {
-    let x = rand::thread_rng().gen_range(-300..=300) as f32;
-    let y = rand::thread_rng().gen_range(-300..=300) as f32;
-    Vec2::new(x, y)
+fn food_velocity(food: &Food) -> Vec2 {
+    Vec2::from_angle(food.direction) * food.speed
+}
+
+/// Sums forces of all steering behaviours of the food and changes its direction and speed.
+fn food_steer(food: &mut Food, steering: &FoodSteering, threats: &[Vec2], neighbours: &[SteeringNeighbour], time_delta_seconds: f32) {
+    let velocity = food_velocity(food);
+    let mut force = Vec2::ZERO;
+    if let Some(wander) = &steering.wander {
+        let random = rand::thread_rng().gen_range(-1.0..=1.0);
+        force += steering_wander(velocity, &mut food.wander_angle, wander, random, time_delta_seconds);
+    }
+    if let Some(flee) = &steering.flee {
+        force += steering_flee(food.pos, threats, flee);
+    }
+    if let Some(flock) = &steering.flock {
+        force += steering_flock(food.pos, velocity, neighbours, flock);
+    }
+    let new_velocity = steering_apply(velocity, force, steering, time_delta_seconds);
+    food.speed = new_velocity.length();
+    if food.speed > 0.0 {
+        food.direction = new_velocity.to_angle();
+    }
+}
+
+/// Small food which does not move and disappears when eaten. Boosting snakes drop it behind.
+pub fn food_pellet_spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, pos: Vec2, nutrition: f32) {
+    let radius = 5.0;
+    commands.spawn((
+        SpriteBundle {
+            texture: asset_server.load("Food.png"),
+            // scale is set by food_update when the image is loaded
+            transform: Transform::from_xyz(pos.x, pos.y, 0.0).with_scale(Vec3::ZERO),
+            ..default()
+        },
+        Food {
+            pos,
+            direction: 0.0,
+            radius,
+            color: new_food_color(),
+            nutrition,
+            score: 0,
+            speed: 0.0,
+            effect: None,
+            kind: None,
+            steering: None,
+            wander_angle: 0.0,
+            visual: FoodVisual::default(),
+            age: 0.0,
+        }
+    ));
 }
-fn new_food_direction(last_direction: f32) -> f32 {
-    let num = rand::thread_rng().gen_range(-10.0..= 10.0) as f32;
-    let new_direction = last_direction - consts::PI + (num / 10.0);
-    new_direction
+
+/// Pellets along the body of a dead snake, nutrition is shared between them equally.
+pub fn food_spawn_on_death(commands: &mut Commands, asset_server: &Res<AssetServer>, node_positions: &[Vec2], nutrition: f32) {
+    if node_positions.is_empty() {
+        return;
+    }
+    let pellet_nutrition = nutrition / node_positions.len() as f32;
+    for pos in food_spawn_positions_along_body(node_positions, 10.0, &mut rand::thread_rng()) {
+        food_pellet_spawn(commands, asset_server, pos, pellet_nutrition);
+    }
 }
+
 fn new_food_color() -> Srgba {
     let mut rnd = rand::thread_rng();
     let hue: f32 = rnd.gen();
@@ -116,32 +201,35 @@ fn new_food_color() -> Srgba {
     color
 }
 
-fn snake_eats_food(
-    snake: &SnakeModel,
-    food: &Food
-) -> bool {
-    let distance_vector = snake.head_pos - food.pos;
-    let distance_between = ((distance_vector.x * distance_vector.x) + (distance_vector.y * distance_vector.y)).sqrt();
-    distance_between < food.radius + snake.head_radius
-}
-fn food_on_bound(food: &mut Food, bound_query: &Query<&mut Bound> ) {
+/// Food touching the bound is pushed back and reflected about the wall normal.
+fn food_on_bound(food: &mut Food, bound_query: &Query<&Bound>) {
     for bound in bound_query {
-        let origin = Vec2::new(0.0, 0.0);
-        let distance_from_origin_to_food: f32 = {
-            let distance_vector = origin - food.pos;
-            ((distance_vector.x * distance_vector.x) + (distance_vector.y * distance_vector.y)).sqrt()
-        };
-        if distance_from_origin_to_food > (bound.radius - (food.radius * 2.0 )) {
-            food.direction = new_food_direction(food.direction)
+        let (pos, velocity) = arena_bounce(&bound.shape, food.pos, food.radius, food_velocity(food));
+        food.pos = pos;
+        if velocity.length_squared() > 0.0 {
+            food.direction = velocity.to_angle();
         }
     }
 }
 
-fn draw_food(food: &mut Food, gizmos: &mut Gizmos, query: &Query<&GridVisualDiagnostic>) {
+/// Food is pushed out of obstacles and reflected like from the bound.
+fn food_on_obstacles(food: &mut Food, spatial_hash: &SpatialHash, obstacle_query: &Query<&Obstacle>) {
+    for entry in spatial_hash_query(spatial_hash, food.pos, food.radius) {
+        let SpatialItem::Obstacle(entity) = entry.item else { continue; };
+        let Ok(obstacle) = obstacle_query.get(entity) else { continue; };
+        let (pos, velocity) = obstacle_bounce(&obstacle.shape, food.pos, food.radius, food_velocity(food));
+        food.pos = pos;
+        if velocity.length_squared() > 0.0 {
+            food.direction = velocity.to_angle();
+        }
+    }
+}
+
+fn draw_food(food: &mut Food, time_delta_seconds: f32, gizmos: &mut Gizmos, query: &Query<&GridVisualDiagnostic>) {
     let food_move = {
         let x = f32::cos(food.direction);
         let y = f32::sin(food.direction);
-        Vec2::new(x, y)
+        Vec2::new(x, y) * food.speed * time_delta_seconds
     };
     food.pos += food_move;
     
@@ -149,37 +237,90 @@ fn draw_food(food: &mut Food, gizmos: &mut Gizmos, query: &Query<&GridVisualDiag
         gizmos.circle_2d(food.pos, food.radius, food.color);
     }
 }
+#[allow(clippy::too_many_arguments)]
 fn food_update(
+    mut commands: Commands,
     mut gizmos: Gizmos,
-    bound_query: Query<&mut Bound>,
-    mut food_query: Query<(&mut Food, &mut Transform)>,
-    mut snake_query: Query<&mut SnakeModel>,
-    mut score_query: Query<(&mut Text, &mut Score)>,
+    bound_query: Query<&Bound>,
+    mut food_query: Query<(Entity, &mut Food, &mut Transform, &Handle<Image>)>,
+    mut snake_query: Query<(Entity, &mut SnakeModel, &Player)>,
+    mut score_query: Query<&mut Score>,
     query: Query<&GridVisualDiagnostic>,
-
+    time: Res<Time>,
+    spatial_hash: Res<SpatialHash>,
+    images: Res<Assets<Image>>,
+    obstacle_query: Query<&Obstacle>,
 ) {
-    for (mut food, mut transform) in &mut food_query {
-        for mut snake in &mut snake_query {
-            if snake_eats_food(&snake, &food) {
-                food.direction = new_food_direction(food.direction);
-                food.pos = new_food_position();
-                food.color = new_food_color();
-
-                for (mut text, mut score) in &mut score_query {
-                    score.score_num += 1;
-                    let score_string = score.score_num.to_string();
-                    text.sections[0].value = format!("Score: {score_string}");
+    let threats: Vec<Vec2> = snake_query.iter().map(|(_, snake, _)| snake.head_pos).collect();
+    // kind and velocity of every food before it moves in this frame
+    let food_snapshot: HashMap<Entity, (Option<usize>, Vec2)> = food_query.iter()
+        .map(|(entity, food, ..)| (entity, (food.kind, food_velocity(food))))
+        .collect();
+
+    // food which touches a snake head, the first snake found eats it
+    let mut eaten_by: HashMap<Entity, Entity> = HashMap::new();
+    for (snake_entity, snake, _) in &snake_query {
+        for entry in spatial_hash_query(&spatial_hash, snake.head_pos, snake.head_radius) {
+            if let SpatialItem::Food(food_entity) = entry.item {
+                eaten_by.entry(food_entity).or_insert(snake_entity);
+            }
+        }
+    }
+
+    for (food_entity, mut food, mut transform, texture) in &mut food_query {
+        let mut eaten = false;
+        if let Some(snake_entity) = eaten_by.get(&food_entity) {
+            if let Ok((_, mut snake, player)) = snake_query.get_mut(*snake_entity) {
+                for mut score in score_query.iter_mut().filter(|score| score.player == player.index) {
+                    // hazardous food can take score, but not below zero
+                    score.score_num = i32::max(score.score_num + food.score, 0);
+                }
+                snake_swallow(&mut snake, food.nutrition);
+                if let Some(effect) = &food.effect {
+                    snake_effect_add(&mut snake, effect);
                 }
-                snake.size += 1.0;
-                break;
+                eaten = true;
             }
         }
+        // the spawner replaces eaten food in a new place
+        if eaten {
+            commands.entity(food_entity).despawn();
+            continue;
+        }
 
-        draw_food(&mut food, &mut gizmos, &query);
+        for (_, snake, _) in &snake_query {
+            let pull = snake_magnet_pull(snake, food.pos, time.delta_seconds());
+            food.pos += pull;
+        }
+
+        if let Some(steering) = food.steering.clone() {
+            let flock_radius = steering.flock.as_ref().map_or(0.0, |flock| flock.radius);
+            let neighbours: Vec<SteeringNeighbour> = spatial_hash_query(&spatial_hash, food.pos, flock_radius).iter()
+                .filter_map(|entry| match entry.item {
+                    SpatialItem::Food(entity) if entity != food_entity => {
+                        let (kind, velocity) = food_snapshot.get(&entity)?;
+                        if kind.is_some() && *kind == food.kind {
+                            Some(SteeringNeighbour { pos: entry.pos, velocity: *velocity })
+                        }
+                        else { None }
+                    }
+                    _ => None,
+                })
+                .collect();
+            food_steer(&mut food, &steering, &threats, &neighbours, time.delta_seconds());
+        }
+
+        draw_food(&mut food, time.delta_seconds(), &mut gizmos, &query);
 
         food_on_bound(&mut food, &bound_query);
+        food_on_obstacles(&mut food, &spatial_hash, &obstacle_query);
 
         transform.translation = Vec3::new(food.pos.x, food.pos.y, 0.0); 
-        transform.rotation = Quat::from_rotation_z(food.direction + consts::PI / 2.0 + consts::PI);
+        food.age += time.delta_seconds();
+        transform.rotation = food_sprite_rotation(&food.visual, food.direction, food.age);
+        if let Some(image) = images.get(texture) {
+            let scale = food_sprite_scale(food.radius, image.size_f32()) * food_sprite_pulse(&food.visual, food.age);
+            transform.scale = Vec3::new(scale, scale, scale);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,316 @@
+use bevy::prelude::{Color, Component, Quat, Vec2};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::food_steering::FoodSteering;
+
+pub const FOOD_KINDS_PATH: &str = "assets/food_kinds.ron";
+
+/// Effect applied to the snake which eats the food.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub enum FoodEffect {
+    // multiplies movement speed for duration seconds
+    SpeedBoost { multiplier: f32, duration: f32 },
+    // multiplies rotation speed, so the snake turns tighter
+    TightTurn { multiplier: f32, duration: f32 },
+    // pulls food within radius toward the head, speed is in units per second
+    Magnet { radius: f32, speed: f32, duration: f32 },
+    // snake passes through its own body
+    Ghost { duration: f32 },
+    // instantly removes amount of nodes from the tail
+    Shrink { amount: f32 },
+    // left and right, forward and backward are swapped
+    ReverseControls { duration: f32 },
+}
+
+pub fn food_effect_duration(effect: &FoodEffect) -> f32 {
+    match effect {
+        FoodEffect::SpeedBoost { duration, .. } => *duration,
+        FoodEffect::TightTurn { duration, .. } => *duration,
+        FoodEffect::Magnet { duration, .. } => *duration,
+        FoodEffect::Ghost { duration } => *duration,
+        FoodEffect::Shrink { .. } => 0.0,
+        FoodEffect::ReverseControls { duration } => *duration,
+    }
+}
+
+pub fn food_effect_name(effect: &FoodEffect) -> &'static str {
+    match effect {
+        FoodEffect::SpeedBoost { .. } => "Speed",
+        FoodEffect::TightTurn { .. } => "Turn",
+        FoodEffect::Magnet { .. } => "Magnet",
+        FoodEffect::Ghost { .. } => "Ghost",
+        FoodEffect::Shrink { .. } => "Shrink",
+        FoodEffect::ReverseControls { .. } => "Reverse",
+    }
+}
+
+/// Periodic change of the sprite scale.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FoodPulse {
+    // scale changes from 1 - amplitude to 1 + amplitude
+    pub amplitude: f32,
+    // pulses per second
+    pub frequency: f32,
+}
+
+/// How the food sprite is oriented and animated.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FoodVisual {
+    // direction in the image where the food looks, in degrees counterclockwise from the image right side
+    #[serde(default = "food_visual_default_forward_degrees")]
+    pub forward_degrees: f32,
+    // sprite rotation on top of the movement direction, degrees per second
+    #[serde(default)]
+    pub spin_degrees_per_second: f32,
+    #[serde(default)]
+    pub pulse: Option<FoodPulse>,
+}
+
+// bundled food images look up
+fn food_visual_default_forward_degrees() -> f32 {
+    90.0
+}
+
+impl Default for FoodVisual {
+    fn default() -> Self {
+        FoodVisual {
+            forward_degrees: food_visual_default_forward_degrees(),
+            spin_degrees_per_second: 0.0,
+            pulse: None,
+        }
+    }
+}
+
+/// Food definition loaded from data.
+#[derive(Deserialize, Clone, Debug)]
+pub struct FoodKind {
+    pub name: String,
+    // size added to the snake which eats the food
+    pub nutrition: f32,
+    // score added when the food is eaten
+    pub score: i32,
+    pub sprite: String,
+    pub radius: f32,
+    // relative chance to be spawned, kinds with 0 weight are never spawned randomly
+    pub spawn_weight: f32,
+    #[serde(default)]
+    pub effect: Option<FoodEffect>,
+    // sprite color multiplier (red, green, blue), hazardous food uses it to look different
+    #[serde(default)]
+    pub tint: Option<(f32, f32, f32)>,
+    // food without steering moves in a straight line
+    #[serde(default)]
+    pub steering: Option<FoodSteering>,
+    #[serde(default)]
+    pub visual: FoodVisual,
+}
+
+/// All food kinds of the game and the weighted distribution to pick them.
+#[derive(Component)]
+pub struct FoodKinds {
+    pub kinds: Vec<FoodKind>,
+    weights: WeightedIndex<f32>,
+}
+
+pub fn food_kinds_default() -> Vec<FoodKind> {
+    vec![FoodKind {
+        name: "berry".to_string(),
+        nutrition: 1.0,
+        score: 1,
+        sprite: "Food.png".to_string(),
+        radius: 10.0,
+        spawn_weight: 1.0,
+        effect: None,
+        tint: None,
+        steering: None,
+        visual: FoodVisual::default(),
+    }]
+}
+
+pub fn food_kinds_parse(text: &str) -> Result<Vec<FoodKind>, String> {
+    ron::from_str(text).map_err(|e| e.to_string())
+}
+
+pub fn food_kinds_load(path: &str) -> Result<Vec<FoodKind>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    food_kinds_parse(&text)
+}
+
+pub fn food_kinds_new(kinds: Vec<FoodKind>) -> Result<FoodKinds, String> {
+    let weights = WeightedIndex::new(kinds.iter().map(|kind| kind.spawn_weight)).map_err(|e| e.to_string())?;
+    Ok(FoodKinds { kinds, weights })
+}
+
+/// Kinds with the given names, all kinds if names are empty.
+pub fn food_kinds_filter(kinds: Vec<FoodKind>, names: &[String]) -> Vec<FoodKind> {
+    if names.is_empty() {
+        return kinds;
+    }
+    kinds.into_iter().filter(|kind| names.contains(&kind.name)).collect()
+}
+
+/// Loads food kinds from the data file and keeps only the named ones.
+/// If the file is missing or broken or no kind is left default kinds are used.
+pub fn food_kinds_from_file_or_default(path: &str, names: &[String]) -> FoodKinds {
+    match food_kinds_load(path).map(|kinds| food_kinds_filter(kinds, names)).and_then(food_kinds_new) {
+        Ok(food_kinds) => {
+            let names: Vec<&str> = food_kinds.kinds.iter().map(|kind| kind.name.as_str()).collect();
+            println!("Food kinds loaded: {}", names.join(", "));
+            food_kinds
+        }
+        Err(error) => {
+            println!("Food kinds are not loaded, default kinds are used: {error}");
+            food_kinds_new(food_kinds_default()).unwrap()
+        }
+    }
+}
+
+pub fn food_kind_sprite_color(kind: &FoodKind) -> Color {
+    match kind.tint {
+        None => Color::WHITE,
+        Some((red, green, blue)) => Color::srgb(red, green, blue),
+    }
+}
+
+/// Scale which makes the longer side of the image as long as the food diameter.
+pub fn food_sprite_scale(radius: f32, image_size: Vec2) -> f32 {
+    let image_size = image_size.max_element();
+    if image_size <= 0.0 { 0.0 } else { radius * 2.0 / image_size }
+}
+
+/// Scale multiplier of the pulse animation after age seconds.
+pub fn food_sprite_pulse(visual: &FoodVisual, age: f32) -> f32 {
+    match &visual.pulse {
+        None => 1.0,
+        Some(pulse) => 1.0 + pulse.amplitude * f32::sin(age * pulse.frequency * std::f32::consts::TAU),
+    }
+}
+
+/// Sprite rotation which turns the image forward side to the movement direction and adds the spin.
+pub fn food_sprite_rotation(visual: &FoodVisual, direction: f32, age: f32) -> Quat {
+    let angle = direction - visual.forward_degrees.to_radians() + (visual.spin_degrees_per_second * age).to_radians();
+    Quat::from_rotation_z(angle)
+}
+
+/// Random kind index where every kind has chance proportional to its spawn_weight.
+pub fn food_kind_random(food_kinds: &FoodKinds, rng: &mut impl Rng) -> usize {
+    food_kinds.weights.sample(rng)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_food_kinds_are_valid() {
+        let kinds = food_kinds_load(FOOD_KINDS_PATH).unwrap();
+        assert!(!kinds.is_empty());
+        assert!(food_kinds_new(kinds).is_ok());
+    }
+
+    #[test]
+    fn parse_kind_with_effect() {
+        let text = r#"[
+            (name: "a", nutrition: 2.0, score: 3, sprite: "A.png", radius: 5.0, spawn_weight: 1.0),
+            (name: "b", nutrition: 1.0, score: 1, sprite: "B.png", radius: 5.0, spawn_weight: 0.0,
+                effect: Some(SpeedBoost(multiplier: 2.0, duration: 3.0))),
+            (name: "c", nutrition: 0.0, score: -3, sprite: "C.png", radius: 5.0, spawn_weight: 1.0,
+                effect: Some(Shrink(amount: 2.0)), tint: Some((0.5, 1.0, 0.0))),
+        ]"#;
+        let kinds = food_kinds_parse(text).unwrap();
+        assert_eq!(kinds.len(), 3);
+        assert_eq!(kinds[0].effect, None);
+        assert_eq!(kinds[0].tint, None);
+        assert_eq!(kinds[2].score, -3);
+        assert_eq!(kinds[2].tint, Some((0.5, 1.0, 0.0)));
+        assert_eq!(kinds[1].effect, Some(FoodEffect::SpeedBoost { multiplier: 2.0, duration: 3.0 }));
+    }
+
+    #[test]
+    fn parse_all_effects() {
+        let text = r#"[
+            Some(SpeedBoost(multiplier: 2.0, duration: 1.0)),
+            Some(TightTurn(multiplier: 2.0, duration: 2.0)),
+            Some(Magnet(radius: 100.0, speed: 50.0, duration: 3.0)),
+            Some(Ghost(duration: 4.0)),
+            Some(Shrink(amount: 2.0)),
+            Some(ReverseControls(duration: 5.0)),
+            None,
+        ]"#;
+        let effects: Vec<Option<FoodEffect>> = ron::from_str(text).unwrap();
+        let durations: Vec<f32> = effects.iter().flatten().map(food_effect_duration).collect();
+        assert_eq!(durations, vec![1.0, 2.0, 3.0, 4.0, 0.0, 5.0]);
+        assert_eq!(effects[6], None);
+    }
+
+    #[test]
+    fn parse_visual() {
+        let text = r#"[
+            (name: "a", nutrition: 1.0, score: 1, sprite: "A.png", radius: 5.0, spawn_weight: 1.0),
+            (name: "b", nutrition: 1.0, score: 1, sprite: "B.png", radius: 5.0, spawn_weight: 1.0,
+                visual: (forward_degrees: 0.0, spin_degrees_per_second: 90.0, pulse: Some((amplitude: 0.1, frequency: 2.0)))),
+            (name: "c", nutrition: 1.0, score: 1, sprite: "C.png", radius: 5.0, spawn_weight: 1.0,
+                visual: (spin_degrees_per_second: 45.0)),
+        ]"#;
+        let kinds = food_kinds_parse(text).unwrap();
+        assert_eq!(kinds[0].visual, FoodVisual::default());
+        assert_eq!(kinds[1].visual.pulse, Some(FoodPulse { amplitude: 0.1, frequency: 2.0 }));
+        assert_eq!(kinds[2].visual.forward_degrees, 90.0);
+    }
+
+    #[test]
+    fn scale_uses_longer_image_side() {
+        assert!((food_sprite_scale(10.0, Vec2::new(57.0, 69.0)) - 20.0 / 69.0).abs() < 0.0001);
+        assert_eq!(food_sprite_scale(10.0, Vec2::ZERO), 0.0);
+    }
+
+    #[test]
+    fn pulse_changes_scale_around_one() {
+        let visual = FoodVisual { pulse: Some(FoodPulse { amplitude: 0.2, frequency: 1.0 }), ..FoodVisual::default() };
+        assert!((food_sprite_pulse(&visual, 0.25) - 1.2).abs() < 0.0001);
+        assert!((food_sprite_pulse(&visual, 0.75) - 0.8).abs() < 0.0001);
+        assert_eq!(food_sprite_pulse(&FoodVisual::default(), 0.25), 1.0);
+    }
+
+    #[test]
+    fn rotation_turns_forward_side_to_direction() {
+        let visual = FoodVisual::default();
+        // image up side is turned to the right
+        let forward = food_sprite_rotation(&visual, 0.0, 0.0) * bevy::math::Vec3::Y;
+        assert!((forward.x - 1.0).abs() < 0.0001);
+        let spinning = FoodVisual { spin_degrees_per_second: 90.0, ..FoodVisual::default() };
+        let forward = food_sprite_rotation(&spinning, 0.0, 1.0) * bevy::math::Vec3::Y;
+        assert!((forward.y - 1.0).abs() < 0.0001);
+    }
+
+    #[test]
+    fn zero_weight_kind_is_never_picked() {
+        let mut kinds = food_kinds_default();
+        let mut never = kinds[0].clone();
+        never.spawn_weight = 0.0;
+        kinds.push(never);
+        let food_kinds = food_kinds_new(kinds).unwrap();
+        for _ in 0..100 {
+            assert_eq!(food_kind_random(&food_kinds, &mut rand::thread_rng()), 0);
+        }
+    }
+
+    #[test]
+    fn filter_by_names() {
+        let kinds = food_kinds_load(FOOD_KINDS_PATH).unwrap();
+        let count = kinds.len();
+        assert_eq!(food_kinds_filter(kinds.clone(), &[]).len(), count);
+        let filtered = food_kinds_filter(kinds, &["berry".to_string(), "no such berry".to_string()]);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].name, "berry");
+    }
+
+    #[test]
+    fn all_zero_weights_are_rejected() {
+        let mut kinds = food_kinds_default();
+        kinds[0].spawn_weight = 0.0;
+        assert!(food_kinds_new(kinds).is_err());
+    }
+}
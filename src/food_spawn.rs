@@ -0,0 +1,262 @@
+use bevy::math::Vec2;
+use bevy::prelude::Component;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+use crate::arena::*;
+
+// random positions tried to find a place far enough from snakes
+const AWAY_FROM_SNAKES_ATTEMPTS: i32 = 20;
+// random positions in the box around the arena tried to find one inside the arena shape
+const INSIDE_ARENA_ATTEMPTS: i32 = 50;
+
+/// How positions of new food are chosen.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum FoodSpawnPolicy {
+    // anywhere inside the bound
+    Uniform,
+    // inside the bound, but not closer than min_distance to any snake head
+    AwayFromSnakes { min_distance: f32 },
+    // inside one of count random patches with the given radius
+    Clusters { count: usize, radius: f32 },
+}
+
+/// Keeps the number of food inside the bound close to the target density.
+#[derive(Component)]
+pub struct FoodSpawner {
+    pub policy: FoodSpawnPolicy,
+    // food count for every 100 x 100 area of the bound
+    pub target_density: f32,
+    // maximum number of food spawned every second when there is not enough food
+    pub spawn_per_second: f32,
+    // food which should be spawned but waits for the next whole number
+    pub spawn_accumulator: f32,
+    // true until the bound is filled first time
+    pub initial_fill: bool,
+    // centers of patches for Clusters policy, chosen on the first spawn
+    pub cluster_centers: Vec<Vec2>,
+    // food spawns only inside these shapes, in the whole bound if empty
+    pub regions: Vec<ArenaShape>,
+}
+
+pub fn food_spawner_new(policy: FoodSpawnPolicy) -> FoodSpawner {
+    FoodSpawner {
+        policy,
+        target_density: 0.2,
+        spawn_per_second: 2.0,
+        spawn_accumulator: 0.0,
+        initial_fill: true,
+        cluster_centers: vec![],
+        regions: vec![],
+    }
+}
+
+pub fn food_target_count(spawner: &FoodSpawner, bound_area: f32) -> usize {
+    (spawner.target_density * bound_area / (100.0 * 100.0)).round() as usize
+}
+
+/// Number of food to spawn now. Whole missing amount is spawned on the first fill,
+/// after that food appears not faster than spawn_per_second.
+pub fn food_spawn_count(spawner: &mut FoodSpawner, current_count: usize, target_count: usize, time_delta_seconds: f32) -> usize {
+    let missing = target_count.saturating_sub(current_count);
+    if spawner.initial_fill {
+        spawner.initial_fill = false;
+        return missing;
+    }
+    if missing == 0 {
+        spawner.spawn_accumulator = 0.0;
+        return 0;
+    }
+    spawner.spawn_accumulator += spawner.spawn_per_second * time_delta_seconds;
+    let count = usize::min(spawner.spawn_accumulator.floor() as usize, missing);
+    spawner.spawn_accumulator -= count as f32;
+    count
+}
+
+/// Uniform random position inside the circle, margin is kept from the circle edge.
+pub fn spawn_position_uniform(center: Vec2, radius: f32, margin: f32, rng: &mut impl Rng) -> Vec2 {
+    let radius = f32::max(radius - margin, 0.0);
+    // square root makes positions uniform by area, not crowded near the center
+    let distance = radius * rng.gen_range(0.0f32..=1.0).sqrt();
+    let angle = rng.gen_range(0.0..PI * 2.0);
+    center + Vec2::from_angle(angle) * distance
+}
+
+/// Uniform random position inside the arena shape, margin is kept from the outline.
+pub fn spawn_position_in_arena(shape: &ArenaShape, margin: f32, rng: &mut impl Rng) -> Vec2 {
+    if let ArenaShape::Circle { center, radius } = shape {
+        return spawn_position_uniform(*center, *radius, margin, rng);
+    }
+    let (min, max) = arena_bounding_box(shape);
+    let mut pos = min;
+    for _ in 0..INSIDE_ARENA_ATTEMPTS {
+        pos = Vec2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y));
+        if arena_signed_distance(shape, pos) <= -margin {
+            return pos;
+        }
+    }
+    arena_push_inside(shape, pos, margin)
+}
+
+/// Uniform position which is not closer than min_distance to any of the heads.
+/// If no such position is found the last tried one is returned.
+pub fn spawn_position_away_from(shape: &ArenaShape, margin: f32, heads: &[Vec2], min_distance: f32, rng: &mut impl Rng) -> Vec2 {
+    let mut pos = spawn_position_in_arena(shape, margin, rng);
+    for _ in 1..AWAY_FROM_SNAKES_ATTEMPTS {
+        if heads.iter().all(|head| head.distance(pos) >= min_distance) {
+            break;
+        }
+        pos = spawn_position_in_arena(shape, margin, rng);
+    }
+    pos
+}
+
+/// Uniform position inside a random cluster.
+pub fn spawn_position_in_clusters(cluster_centers: &[Vec2], cluster_radius: f32, rng: &mut impl Rng) -> Vec2 {
+    let cluster_center = cluster_centers[rng.gen_range(0..cluster_centers.len())];
+    spawn_position_uniform(cluster_center, cluster_radius, 0.0, rng)
+}
+
+/// Random spawn region with chance proportional to its area, or the bound shape without regions.
+pub fn food_spawn_shape<'a>(regions: &'a [ArenaShape], bound_shape: &'a ArenaShape, rng: &mut impl Rng) -> &'a ArenaShape {
+    match WeightedIndex::new(regions.iter().map(arena_area)) {
+        Ok(weights) => &regions[weights.sample(rng)],
+        Err(_) => bound_shape,
+    }
+}
+
+/// Position for new food according to the spawner policy.
+pub fn food_spawn_position(spawner: &mut FoodSpawner, bound_shape: &ArenaShape, food_radius: f32, heads: &[Vec2], rng: &mut impl Rng) -> Vec2 {
+    let margin = food_radius * 2.0;
+    let regions = spawner.regions.clone();
+    let shape = food_spawn_shape(&regions, bound_shape, rng);
+    match spawner.policy.clone() {
+        FoodSpawnPolicy::Uniform => spawn_position_in_arena(shape, margin, rng),
+        FoodSpawnPolicy::AwayFromSnakes { min_distance } => spawn_position_away_from(shape, margin, heads, min_distance, rng),
+        FoodSpawnPolicy::Clusters { count, radius } => {
+            if spawner.cluster_centers.is_empty() {
+                spawner.cluster_centers = (0..count.max(1))
+                    .map(|_| spawn_position_in_arena(shape, radius + margin, rng))
+                    .collect();
+            }
+            spawn_position_in_clusters(&spawner.cluster_centers, radius, rng)
+        }
+    }
+}
+
+/// One position near every node of a dead snake, shifted randomly by up to spread.
+pub fn food_spawn_positions_along_body(node_positions: &[Vec2], spread: f32, rng: &mut impl Rng) -> Vec<Vec2> {
+    node_positions.iter()
+        .map(|node_pos| spawn_position_uniform(*node_pos, spread, 0.0, rng))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn uniform_stays_inside_circle_with_margin() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let center = Vec2::new(100.0, -50.0);
+        for _ in 0..1000 {
+            let pos = spawn_position_uniform(center, 500.0, 20.0, &mut rng);
+            assert!(pos.distance(center) <= 480.0);
+        }
+    }
+
+    #[test]
+    fn uniform_is_not_crowded_in_center() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let inner = (0..10000)
+            .map(|_| spawn_position_uniform(Vec2::ZERO, 100.0, 0.0, &mut rng))
+            .filter(|pos| pos.length() < 50.0)
+            .count();
+        // inner circle has a quarter of the area
+        assert!(inner > 2200 && inner < 2800);
+    }
+
+    #[test]
+    fn away_from_snakes_keeps_distance() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let heads = [Vec2::ZERO, Vec2::new(200.0, 0.0)];
+        let shape = ArenaShape::Circle { center: Vec2::ZERO, radius: 500.0 };
+        for _ in 0..200 {
+            let pos = spawn_position_away_from(&shape, 0.0, &heads, 100.0, &mut rng);
+            assert!(heads.iter().all(|head| head.distance(pos) >= 100.0));
+        }
+    }
+
+    #[test]
+    fn clusters_are_reused() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut spawner = food_spawner_new(FoodSpawnPolicy::Clusters { count: 3, radius: 30.0 });
+        for _ in 0..100 {
+            let shape = ArenaShape::Circle { center: Vec2::ZERO, radius: 500.0 };
+            let pos = food_spawn_position(&mut spawner, &shape, 5.0, &[], &mut rng);
+            assert!(spawner.cluster_centers.iter().any(|center| center.distance(pos) <= 30.0));
+        }
+        assert_eq!(spawner.cluster_centers.len(), 3);
+    }
+
+    #[test]
+    fn regions_are_used_instead_of_bound() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut spawner = food_spawner_new(FoodSpawnPolicy::Uniform);
+        spawner.regions = vec![
+            ArenaShape::Circle { center: Vec2::new(-200.0, 0.0), radius: 20.0 },
+            ArenaShape::Rect { center: Vec2::new(200.0, 0.0), half_size: Vec2::new(20.0, 20.0) },
+        ];
+        let bound = ArenaShape::Circle { center: Vec2::ZERO, radius: 500.0 };
+        for _ in 0..100 {
+            let pos = food_spawn_position(&mut spawner, &bound, 1.0, &[], &mut rng);
+            assert!(spawner.regions.iter().any(|region| arena_contains(region, pos)));
+        }
+    }
+
+    #[test]
+    fn along_body_is_near_nodes() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let nodes = [Vec2::ZERO, Vec2::new(20.0, 0.0), Vec2::new(40.0, 0.0)];
+        let positions = food_spawn_positions_along_body(&nodes, 5.0, &mut rng);
+        assert_eq!(positions.len(), 3);
+        for (pos, node) in positions.iter().zip(nodes.iter()) {
+            assert!(pos.distance(*node) <= 5.0);
+        }
+    }
+
+    #[test]
+    fn target_count_from_density() {
+        let mut spawner = food_spawner_new(FoodSpawnPolicy::Uniform);
+        spawner.target_density = 1.0;
+        assert_eq!(food_target_count(&spawner, 30000.0), 3);
+    }
+
+    #[test]
+    fn arena_position_is_inside_shape() {
+        let mut rng = StdRng::seed_from_u64(6);
+        let shape = ArenaShape::Polygon { points: vec![
+            Vec2::new(0.0, 0.0), Vec2::new(300.0, 0.0), Vec2::new(300.0, 300.0), Vec2::new(200.0, 300.0),
+            Vec2::new(200.0, 100.0), Vec2::new(100.0, 100.0), Vec2::new(100.0, 300.0), Vec2::new(0.0, 300.0),
+        ]};
+        for _ in 0..500 {
+            let pos = spawn_position_in_arena(&shape, 10.0, &mut rng);
+            assert!(arena_signed_distance(&shape, pos) <= -10.0);
+        }
+    }
+
+    #[test]
+    fn first_fill_then_limited_rate() {
+        let mut spawner = food_spawner_new(FoodSpawnPolicy::Uniform);
+        spawner.spawn_per_second = 2.0;
+        assert_eq!(food_spawn_count(&mut spawner, 0, 10, 0.016), 10);
+        assert_eq!(food_spawn_count(&mut spawner, 8, 10, 0.25), 0);
+        assert_eq!(food_spawn_count(&mut spawner, 8, 10, 0.25), 1);
+        assert_eq!(food_spawn_count(&mut spawner, 9, 10, 5.0), 1);
+        assert_eq!(food_spawn_count(&mut spawner, 10, 10, 5.0), 0);
+    }
+}
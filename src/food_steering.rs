@@ -0,0 +1,180 @@
+use bevy::math::Vec2;
+use serde::Deserialize;
+
+/// Random change of the heading which looks like smooth wandering.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct WanderSteering {
+    // distance to the wander circle in front of the food
+    pub distance: f32,
+    pub radius: f32,
+    // maximum change of the wander angle in radians per second
+    pub jitter: f32,
+    pub strength: f32,
+}
+
+/// Running away from snake heads.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FleeSteering {
+    // snake heads further than radius are ignored
+    pub radius: f32,
+    pub strength: f32,
+}
+
+/// Boids-style flocking with food of the same kind.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FlockSteering {
+    // food of the same kind further than radius is not a neighbour
+    pub radius: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+}
+
+/// Steering behaviours of a food kind. Every behaviour gives a force and all forces are summed.
+#[derive(Deserialize, Clone, Debug, PartialEq)]
+pub struct FoodSteering {
+    // units per second
+    pub max_speed: f32,
+    // units per second squared
+    pub max_force: f32,
+    #[serde(default)]
+    pub wander: Option<WanderSteering>,
+    #[serde(default)]
+    pub flee: Option<FleeSteering>,
+    #[serde(default)]
+    pub flock: Option<FlockSteering>,
+}
+
+/// Neighbour food position and velocity.
+pub struct SteeringNeighbour {
+    pub pos: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Moves the wander angle by random value from -1.0 to 1.0 and returns force toward the point on the wander circle.
+pub fn steering_wander(velocity: Vec2, wander_angle: &mut f32, wander: &WanderSteering, random: f32, time_delta_seconds: f32) -> Vec2 {
+    *wander_angle += random * wander.jitter * time_delta_seconds;
+    let forward = velocity.try_normalize().unwrap_or(Vec2::X);
+    let target = forward * wander.distance + Vec2::from_angle(*wander_angle) * wander.radius;
+    target.normalize_or_zero() * wander.strength
+}
+
+/// Force away from all threats in radius, stronger for closer threats.
+pub fn steering_flee(pos: Vec2, threats: &[Vec2], flee: &FleeSteering) -> Vec2 {
+    let mut result = Vec2::ZERO;
+    for threat in threats {
+        let away = pos - *threat;
+        let distance = away.length();
+        if distance < flee.radius && distance > 0.0 {
+            result += away / distance * flee.strength * (1.0 - distance / flee.radius);
+        }
+    }
+    result
+}
+
+/// Separation from close neighbours, alignment with their velocity and cohesion toward their center.
+pub fn steering_flock(pos: Vec2, velocity: Vec2, neighbours: &[SteeringNeighbour], flock: &FlockSteering) -> Vec2 {
+    let mut separation = Vec2::ZERO;
+    let mut velocity_sum = Vec2::ZERO;
+    let mut pos_sum = Vec2::ZERO;
+    let mut count = 0;
+    for neighbour in neighbours {
+        let away = pos - neighbour.pos;
+        let distance = away.length();
+        if distance >= flock.radius || distance == 0.0 {
+            continue;
+        }
+        separation += away / distance * (1.0 - distance / flock.radius);
+        velocity_sum += neighbour.velocity;
+        pos_sum += neighbour.pos;
+        count += 1;
+    }
+    if count == 0 {
+        return Vec2::ZERO;
+    }
+    let alignment = velocity_sum / count as f32 - velocity;
+    let cohesion = pos_sum / count as f32 - pos;
+    separation.normalize_or_zero() * flock.separation
+        + alignment.normalize_or_zero() * flock.alignment
+        + cohesion.normalize_or_zero() * flock.cohesion
+}
+
+/// New velocity after the force is applied, force and speed are limited.
+pub fn steering_apply(velocity: Vec2, force: Vec2, steering: &FoodSteering, time_delta_seconds: f32) -> Vec2 {
+    let force = force.clamp_length_max(steering.max_force);
+    (velocity + force * time_delta_seconds).clamp_length_max(steering.max_speed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_float_eq(a: f32, b: f32) {
+        let delta_max = 0.001;
+        let c = f32::abs(a - b);
+        assert!(c < delta_max);
+    }
+
+    fn flock_for_test() -> FlockSteering {
+        FlockSteering { radius: 100.0, separation: 1.0, alignment: 1.0, cohesion: 1.0 }
+    }
+
+    #[test]
+    fn wander_without_random_goes_forward() {
+        let wander = WanderSteering { distance: 10.0, radius: 0.0, jitter: 1.0, strength: 5.0 };
+        let mut wander_angle = 0.0;
+        let force = steering_wander(Vec2::new(0.0, 3.0), &mut wander_angle, &wander, 0.0, 1.0);
+        assert_float_eq(force.x, 0.0);
+        assert_float_eq(force.y, 5.0);
+    }
+
+    #[test]
+    fn wander_angle_changes_by_jitter() {
+        let wander = WanderSteering { distance: 10.0, radius: 5.0, jitter: 2.0, strength: 5.0 };
+        let mut wander_angle = 0.0;
+        steering_wander(Vec2::X, &mut wander_angle, &wander, -0.5, 0.5);
+        assert_float_eq(wander_angle, -0.5);
+    }
+
+    #[test]
+    fn flee_from_close_threat_only() {
+        let flee = FleeSteering { radius: 100.0, strength: 10.0 };
+        let force = steering_flee(Vec2::ZERO, &[Vec2::new(50.0, 0.0), Vec2::new(0.0, 500.0)], &flee);
+        assert_float_eq(force.x, -5.0);
+        assert_float_eq(force.y, 0.0);
+    }
+
+    #[test]
+    fn flock_without_neighbours_has_no_force() {
+        let neighbours = [SteeringNeighbour { pos: Vec2::new(500.0, 0.0), velocity: Vec2::X }];
+        let force = steering_flock(Vec2::ZERO, Vec2::X, &neighbours, &flock_for_test());
+        assert_eq!(force, Vec2::ZERO);
+    }
+
+    #[test]
+    fn flock_aligns_with_neighbours() {
+        let flock = FlockSteering { radius: 100.0, separation: 0.0, alignment: 1.0, cohesion: 0.0 };
+        let neighbours = [SteeringNeighbour { pos: Vec2::new(10.0, 0.0), velocity: Vec2::Y }];
+        let force = steering_flock(Vec2::ZERO, Vec2::ZERO, &neighbours, &flock);
+        assert_float_eq(force.x, 0.0);
+        assert_float_eq(force.y, 1.0);
+    }
+
+    #[test]
+    fn flock_separation_and_cohesion_are_opposite() {
+        let neighbours = [SteeringNeighbour { pos: Vec2::new(10.0, 0.0), velocity: Vec2::ZERO }];
+        let separation = FlockSteering { radius: 100.0, separation: 1.0, alignment: 0.0, cohesion: 0.0 };
+        let cohesion = FlockSteering { radius: 100.0, separation: 0.0, alignment: 0.0, cohesion: 1.0 };
+        assert!(steering_flock(Vec2::ZERO, Vec2::ZERO, &neighbours, &separation).x < 0.0);
+        assert!(steering_flock(Vec2::ZERO, Vec2::ZERO, &neighbours, &cohesion).x > 0.0);
+    }
+
+    #[test]
+    fn apply_limits_speed_and_force() {
+        let steering = FoodSteering { max_speed: 10.0, max_force: 1.0, wander: None, flee: None, flock: None };
+        let velocity = steering_apply(Vec2::new(5.0, 0.0), Vec2::new(100.0, 0.0), &steering, 1.0);
+        assert_float_eq(velocity.x, 6.0);
+        let velocity = steering_apply(Vec2::new(9.5, 0.0), Vec2::new(1.0, 0.0), &steering, 1.0);
+        assert_float_eq(velocity.x, 10.0);
+    }
+}
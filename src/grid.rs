@@ -7,6 +7,9 @@ use std::f32;
 use bevy::app::{App, Plugin};
 use bevy::color::palettes::css::GREY;
 
+use crate::camera::CameraFollow;
+use crate::highscore::name_entry_closed;
+
 pub struct VisualDiagnosticPlugin;
 
 #[derive(Component)]
@@ -18,7 +21,7 @@ pub struct GridVisualDiagnostic {
 impl Plugin for VisualDiagnosticPlugin{
     fn build (&self, app: &mut App) {
         app.add_systems(Startup, grid_start);
-        app.add_systems(Update, draw_grid);
+        app.add_systems(Update, draw_grid.run_if(name_entry_closed));
     }
 }
 
@@ -44,6 +47,7 @@ pub fn grid_draw_visual_diagnostics_info(query: &Query<&GridVisualDiagnostic>) -
 fn draw_grid(
     mut gizmos: Gizmos,
     mut grid_query: Query<&mut GridVisualDiagnostic>,
+    camera_query: Query<&GlobalTransform, With<CameraFollow>>,
     keyboard_input: Res<ButtonInput<KeyCode>>
 ){
     for mut grid in &mut grid_query {
@@ -52,18 +56,34 @@ fn draw_grid(
         }
 
         if grid.enabled {
-            for i in 1..50 {
-                let start_pos: Vec2 = Vec2::new(-1000.0, -1000.0 + (i as f32 * grid.cell_size));
-                let end_pos: Vec2 = Vec2::new(1000.0, -1000.0 + (i as f32 * grid.cell_size));
-                gizmos.line_2d(start_pos, end_pos, GREY);
-            }
-            for i in 1..50 {
-                let start_pos: Vec2 = Vec2::new(-1000.0 + (i as f32 * grid.cell_size), -1000.0);
-                let end_pos: Vec2 = Vec2::new(-1000.0 + (i as f32 * grid.cell_size), 1000.0);
-                gizmos.line_2d(start_pos, end_pos, GREY);
-            }
-            gizmos.line_2d(Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0), RED);
-            gizmos.line_2d(Vec2::new(5.0, -5.0), Vec2::new(-5.0, 5.0), RED);
+            grid_draw_lines(&mut gizmos, grid.cell_size, grid_camera_center(&camera_query));
         }   
     }
 }
+
+/// Position the grid is drawn around, the world origin without a camera.
+pub fn grid_camera_center(camera_query: &Query<&GlobalTransform, With<CameraFollow>>) -> Vec2 {
+    camera_query.iter().next().map_or(Vec2::ZERO, |transform| transform.translation().truncate())
+}
+
+/// Grid lines in the 2000 x 2000 square around the center and the origin cross, also used by the level editor.
+/// Lines stay on multiples of cell_size when the center moves.
+pub fn grid_draw_lines(gizmos: &mut Gizmos, cell_size: f32, center: Vec2) {
+    let corner = (center / cell_size).round() * cell_size - Vec2::splat(1000.0);
+    for i in 1..50 {
+        let start_pos: Vec2 = Vec2::new(corner.x, corner.y + (i as f32 * cell_size));
+        let end_pos: Vec2 = Vec2::new(corner.x + 2000.0, corner.y + (i as f32 * cell_size));
+        gizmos.line_2d(start_pos, end_pos, GREY);
+    }
+    for i in 1..50 {
+        let start_pos: Vec2 = Vec2::new(corner.x + (i as f32 * cell_size), corner.y);
+        let end_pos: Vec2 = Vec2::new(corner.x + (i as f32 * cell_size), corner.y + 2000.0);
+        gizmos.line_2d(start_pos, end_pos, GREY);
+    }
+    gizmos.line_2d(Vec2::new(-5.0, -5.0), Vec2::new(5.0, 5.0), RED);
+    gizmos.line_2d(Vec2::new(5.0, -5.0), Vec2::new(-5.0, 5.0), RED);
+}
+
+pub fn grid_cell_size(query: &Query<&GridVisualDiagnostic>) -> f32 {
+    query.iter().next().map_or(50.0, |grid| grid.cell_size)
+}
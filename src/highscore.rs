@@ -0,0 +1,329 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+
+use crate::start::TheGame;
+
+pub const HIGH_SCORES_MAX: usize = 10;
+const HIGH_SCORES_APP_DIR: &str = "Bubbler";
+const HIGH_SCORES_FILE: &str = "high_scores.ron";
+// used when the system has no user data directory
+const HIGH_SCORES_FALLBACK_PATH: &str = "high_scores.ron";
+const NAME_MAX_CHARS: usize = 12;
+
+pub struct HighScorePlugin;
+
+impl Plugin for HighScorePlugin {
+    fn build (&self, app: &mut App) {
+        // the start menu shows the table, so it is loaded before the startup systems
+        app.insert_resource(high_scores_from_file_or_default(&high_scores_path()));
+        app.insert_resource(NameEntry { scores: Vec::new(), name: String::new(), panel: None });
+        app.add_systems(Update, name_entry_update);
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HighScoreEntry {
+    pub name: String,
+    pub score: i32,
+}
+
+/// Best scores from the highest, saved to path after every new entry.
+#[derive(Resource)]
+pub struct HighScores {
+    pub path: PathBuf,
+    pub entries: Vec<HighScoreEntry>,
+}
+
+/// Scores of the last game which get into the table and wait for the player names, the best first.
+/// The game is paused while the name entry panel is shown.
+#[derive(Resource)]
+pub struct NameEntry {
+    // player index and score
+    pub scores: Vec<(usize, i32)>,
+    pub name: String,
+    panel: Option<Entity>,
+}
+
+pub fn high_scores_path() -> PathBuf {
+    high_scores_path_in(dirs::data_dir())
+}
+
+/// File in the user data directory, or in the working directory when there is no such directory.
+pub fn high_scores_path_in(data_dir: Option<PathBuf>) -> PathBuf {
+    match data_dir {
+        Some(dir) => dir.join(HIGH_SCORES_APP_DIR).join(HIGH_SCORES_FILE),
+        None => PathBuf::from(HIGH_SCORES_FALLBACK_PATH),
+    }
+}
+
+pub fn high_scores_parse(text: &str) -> Result<Vec<HighScoreEntry>, String> {
+    let mut entries: Vec<HighScoreEntry> = ron::from_str(text).map_err(|e| e.to_string())?;
+    // the file could be edited by hand
+    entries.sort_by_key(|entry| Reverse(entry.score));
+    entries.truncate(HIGH_SCORES_MAX);
+    Ok(entries)
+}
+
+/// Missing file is an empty table. A broken or unreadable file is kept next to the new one with the .corrupted extension.
+pub fn high_scores_from_file_or_default(path: &Path) -> HighScores {
+    let parsed = match std::fs::read_to_string(path) {
+        Ok(text) => high_scores_parse(&text),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(error) => Err(error.to_string()),
+    };
+    let entries = parsed.unwrap_or_else(|error| {
+        println!("High scores are broken and reset: {}: {error}", path.display());
+        if let Err(error) = std::fs::rename(path, path.with_extension("corrupted")) {
+            println!("Broken high scores are not kept: {error}");
+        }
+        Vec::new()
+    });
+    HighScores { path: path.to_path_buf(), entries }
+}
+
+pub fn high_scores_save(high_scores: &HighScores) -> Result<(), String> {
+    let text = ron::ser::to_string_pretty(&high_scores.entries, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+    if let Some(dir) = high_scores.path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+    }
+    std::fs::write(&high_scores.path, text).map_err(|e| format!("{}: {e}", high_scores.path.display()))
+}
+
+pub fn high_scores_qualifies(entries: &[HighScoreEntry], score: i32) -> bool {
+    score > 0 && (entries.len() < HIGH_SCORES_MAX || entries.iter().any(|entry| score > entry.score))
+}
+
+/// Puts the entry after all entries with the same or better score. Returns its place or None if it is not in the table.
+pub fn high_scores_insert(entries: &mut Vec<HighScoreEntry>, entry: HighScoreEntry) -> Option<usize> {
+    let place = entries.iter().position(|other| entry.score > other.score).unwrap_or(entries.len());
+    if place >= HIGH_SCORES_MAX {
+        return None;
+    }
+    entries.insert(place, entry);
+    entries.truncate(HIGH_SCORES_MAX);
+    Some(place)
+}
+
+/// Lines like "1. name  120" for the start menu.
+pub fn high_scores_text(entries: &[HighScoreEntry]) -> String {
+    if entries.is_empty() {
+        return "No high scores yet".to_string();
+    }
+    entries.iter().enumerate()
+        .map(|(i, entry)| format!("{}. {}  {}", i + 1, entry.name, entry.score))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Player scores of the finished game which get into the table, the best first.
+pub fn high_scores_game_over(entries: &[HighScoreEntry], scores: &[(usize, i32)]) -> Vec<(usize, i32)> {
+    let mut entries = entries.to_vec();
+    let mut best = scores.to_vec();
+    best.sort_by_key(|(_, score)| Reverse(*score));
+    let mut qualified = Vec::new();
+    for (player, score) in best {
+        if !high_scores_qualifies(&entries, score) {
+            continue;
+        }
+        // names are not known yet, the placeholder only keeps the table size right for the next players
+        high_scores_insert(&mut entries, HighScoreEntry { name: String::new(), score });
+        qualified.push((player, score));
+    }
+    qualified
+}
+
+/// Applies a typed key to the name. Returns true when the name is confirmed with Enter.
+pub fn name_entry_edit(name: &mut String, key: &Key) -> bool {
+    match key {
+        Key::Enter => return true,
+        Key::Backspace => { name.pop(); }
+        Key::Space if name.chars().count() < NAME_MAX_CHARS => name.push(' '),
+        Key::Character(text) => {
+            for c in text.chars().filter(|c| !c.is_control()) {
+                if name.chars().count() < NAME_MAX_CHARS {
+                    name.push(c);
+                }
+            }
+        }
+        _ => {}
+    }
+    false
+}
+
+pub fn name_entry_text(player: usize, score: i32, name: &str) -> String {
+    format!("New high score {score}!\nPlayer {} name: {name}_", player + 1)
+}
+
+/// Run condition for systems which use letter keys, so typing a name does not trigger them.
+pub fn name_entry_closed(name_entry: Res<NameEntry>) -> bool {
+    name_entry.scores.is_empty()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn name_entry_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut name_entry: ResMut<NameEntry>,
+    mut high_scores: ResMut<HighScores>,
+    the_game_query: Query<&TheGame>,
+    mut text_query: Query<&mut Text>,
+    mut keyboard_events: EventReader<KeyboardInput>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    let Some(&(player, score)) = name_entry.scores.first() else {
+        keyboard_events.clear();
+        return;
+    };
+    let Ok(the_game) = the_game_query.get_single() else { return; };
+    let panel = match name_entry.panel {
+        Some(panel) => panel,
+        None => {
+            time.pause();
+            let text_style = TextStyle {
+                font: asset_server.load("MovistarTextRegular.ttf"),
+                font_size: 40.0,
+                ..default()
+            };
+            let panel = commands.spawn(
+                TextBundle::from_section(name_entry_text(player, score, ""), text_style)
+                    .with_text_justify(JustifyText::Center)
+                    .with_style(Style { padding: UiRect::all(Val::Px(20.0)), ..default() })
+                    .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.7))
+            ).id();
+            commands.entity(the_game.root_ui_node).add_child(panel);
+            name_entry.panel = Some(panel);
+            name_entry.name.clear();
+            panel
+        }
+    };
+
+    let mut confirmed = false;
+    for event in keyboard_events.read().filter(|event| event.state == ButtonState::Pressed) {
+        confirmed |= name_entry_edit(&mut name_entry.name, &event.logical_key);
+    }
+    if confirmed {
+        let name = match name_entry.name.trim() {
+            "" => format!("Player {}", player + 1),
+            name => name.to_string(),
+        };
+        high_scores_insert(&mut high_scores.entries, HighScoreEntry { name, score });
+        if let Err(error) = high_scores_save(&high_scores) {
+            println!("High scores are not saved: {error}");
+        }
+        name_entry.scores.remove(0);
+        name_entry.name.clear();
+    }
+
+    match name_entry.scores.first() {
+        Some(&(player, score)) => {
+            if let Ok(mut text) = text_query.get_mut(panel) {
+                text.sections[0].value = name_entry_text(player, score, &name_entry.name);
+            }
+        }
+        None => {
+            commands.entity(the_game.root_ui_node).remove_children(&[panel]);
+            commands.entity(panel).despawn_recursive();
+            name_entry.panel = None;
+            time.unpause();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(name: &str, score: i32) -> HighScoreEntry {
+        HighScoreEntry { name: name.to_string(), score }
+    }
+
+    fn test_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bubbler_test_{}_{name}", std::process::id())).join(HIGH_SCORES_FILE)
+    }
+
+    #[test]
+    fn path_falls_back_without_data_dir() {
+        assert_eq!(high_scores_path_in(None), PathBuf::from(HIGH_SCORES_FALLBACK_PATH));
+        assert_eq!(high_scores_path_in(Some(PathBuf::from("/data"))), PathBuf::from("/data/Bubbler/high_scores.ron"));
+    }
+
+    #[test]
+    fn insert_keeps_order_and_limit() {
+        let mut entries: Vec<HighScoreEntry> = (0..HIGH_SCORES_MAX as i32).map(|i| entry("a", 100 - i * 10)).collect();
+        assert_eq!(high_scores_insert(&mut entries, entry("b", 50)), Some(6));
+        assert_eq!(entries.len(), HIGH_SCORES_MAX);
+        // equal score goes after the older entry
+        assert_eq!(entries[5], entry("a", 50));
+        assert_eq!(high_scores_insert(&mut entries, entry("c", 5)), None);
+        assert!(!high_scores_qualifies(&entries, 20));
+        assert!(high_scores_qualifies(&entries, 21));
+        assert!(!high_scores_qualifies(&[], 0));
+    }
+
+    #[test]
+    fn saved_scores_are_loaded_back() {
+        let path = test_path("saved");
+        let high_scores = HighScores { path: path.clone(), entries: vec![entry("ann", 30), entry("bob", 20)] };
+        high_scores_save(&high_scores).unwrap();
+        assert_eq!(high_scores_from_file_or_default(&path).entries, high_scores.entries);
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn corrupted_file_is_kept_aside_and_reset() {
+        let path = test_path("corrupted");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "[(name: \"ann\", score: ").unwrap();
+        let high_scores = high_scores_from_file_or_default(&path);
+        assert!(high_scores.entries.is_empty());
+        assert!(path.with_extension("corrupted").exists());
+        assert!(!path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn file_which_is_not_text_is_kept_aside() {
+        let path = test_path("not_text");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, [0xff, 0xfe, 0x00, 0x80]).unwrap();
+        assert!(high_scores_from_file_or_default(&path).entries.is_empty());
+        assert_eq!(std::fs::read(path.with_extension("corrupted")).unwrap(), vec![0xff, 0xfe, 0x00, 0x80]);
+        assert!(!path.exists());
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn missing_file_is_empty_table() {
+        assert!(high_scores_from_file_or_default(&test_path("missing")).entries.is_empty());
+    }
+
+    #[test]
+    fn game_over_scores_compete_with_each_other() {
+        let mut entries: Vec<HighScoreEntry> = (0..HIGH_SCORES_MAX as i32 - 1).map(|i| entry("a", 100 - i * 10)).collect();
+        // only one place is left, so only the better player enters a name
+        assert_eq!(high_scores_game_over(&entries, &[(0, 12), (1, 15), (2, 0)]), vec![(1, 15)]);
+        entries.truncate(3);
+        assert_eq!(high_scores_game_over(&entries, &[(0, 12), (1, 15), (2, 0)]), vec![(1, 15), (0, 12)]);
+    }
+
+    #[test]
+    fn name_is_typed_and_limited() {
+        let mut name = String::new();
+        for _ in 0..20 {
+            assert!(!name_entry_edit(&mut name, &Key::Character("x".into())));
+        }
+        assert_eq!(name.len(), NAME_MAX_CHARS);
+        name_entry_edit(&mut name, &Key::Backspace);
+        assert_eq!(name.len(), NAME_MAX_CHARS - 1);
+        assert!(name_entry_edit(&mut name, &Key::Enter));
+    }
+
+    #[test]
+    fn table_text_is_numbered() {
+        assert_eq!(high_scores_text(&[entry("ann", 30), entry("bob", 20)]), "1. ann  30\n2. bob  20");
+    }
+}
@@ -0,0 +1,188 @@
+use bevy::prelude::*;
+use bevy::render::camera::ClearColorConfig;
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+use crate::camera::{split_screen_viewports, SplitScreen};
+use crate::food::Score;
+use crate::food_kind::food_effect_name;
+use crate::level::LevelProgress;
+use crate::player::Player;
+use crate::snake_model::{snake_movement_speed, SnakeModel};
+use crate::start::TheGame;
+
+// the HUD camera draws only the UI, nothing in the world is on this layer
+const HUD_RENDER_LAYER: usize = 2;
+// drawn after all player cameras
+const HUD_CAMERA_ORDER: isize = 10;
+// window height at which the UI has its normal size
+const HUD_REFERENCE_HEIGHT: f32 = 720.0;
+// with a shared camera the panels are one under another, each gets this part of the window height
+const HUD_STACKED_PANEL_PERCENT: u32 = 25;
+
+pub struct HudPlugin;
+
+impl Plugin for HudPlugin {
+    fn build (&self, app: &mut App) {
+        app.add_systems(Startup, hud_start);
+        app.add_systems(Update, (hud_panel_spawn_update, hud_scale_update, hud_text_update).chain());
+    }
+}
+
+/// Panel with the values of one player. It is in the top left corner of the player viewport.
+#[derive(Component)]
+pub struct HudPanel {
+    pub player: usize,
+}
+
+/// The UI root spans the whole window, so with split screen it is rendered by a separate camera
+/// which covers all viewports.
+fn hud_start(mut commands: Commands) {
+    let camera = Camera {
+        order: HUD_CAMERA_ORDER,
+        clear_color: ClearColorConfig::None,
+        ..default()
+    };
+    commands.spawn((Camera2dBundle { camera, ..default() }, IsDefaultUiCamera, RenderLayers::layer(HUD_RENDER_LAYER)));
+}
+
+/// Minutes and seconds like 1:05.
+pub fn hud_time(seconds: f32) -> String {
+    let seconds = seconds.max(0.0) as u32;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Top left corner of the player panel in percent of the window. It is the corner of the player viewport,
+/// or a place in the column on the left while the shared camera shows the whole window.
+pub fn hud_panel_position(player: usize, player_count: usize, shared: bool) -> Option<UVec2> {
+    if shared {
+        return Some(UVec2::new(0, HUD_STACKED_PANEL_PERCENT * player as u32));
+    }
+    split_screen_viewports(player_count, UVec2::splat(100)).get(player).map(|(pos, _)| *pos)
+}
+
+/// Text of a player panel. Snake values are not shown while the player waits for a new snake.
+pub fn hud_text(score: i32, snake: Option<&SnakeModel>, seconds: f32) -> String {
+    let mut lines = vec![format!("Score: {score}")];
+    match snake {
+        Some(snake) => {
+            lines.push(format!("Length: {:.1}", snake.size));
+            lines.push(format!("Speed: {:.0}", snake_movement_speed(snake)));
+        }
+        None => {
+            lines.push("Length: -".to_string());
+            lines.push("Speed: -".to_string());
+        }
+    }
+    lines.push(format!("Time: {}", hud_time(seconds)));
+    if let Some(snake) = snake.filter(|snake| !snake.effects.is_empty()) {
+        let effects: Vec<String> = snake.effects.iter()
+            .map(|snake_effect| format!("{} {:.1}s", food_effect_name(&snake_effect.effect), snake_effect.remaining_seconds))
+            .collect();
+        lines.push(effects.join("  "));
+    }
+    lines.join("\n")
+}
+
+/// Every player with a score gets a panel under the UI root.
+fn hud_panel_spawn_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    the_game_query: Query<&TheGame>,
+    score_query: Query<&Score>,
+    panel_query: Query<&HudPanel>,
+) {
+    let Ok(the_game) = the_game_query.get_single() else { return; };
+    for score in &score_query {
+        if panel_query.iter().any(|panel| panel.player == score.player) {
+            continue;
+        }
+        let text_style = TextStyle {
+            font: asset_server.load("MovistarTextRegular.ttf"),
+            font_size: 24.0,
+            ..default()
+        };
+        let panel = commands.spawn((
+            TextBundle::from_section("", text_style).with_style(Style {
+                position_type: PositionType::Absolute,
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            })
+            .with_background_color(Color::srgba(0.0, 0.0, 0.0, 0.4)),
+            HudPanel { player: score.player },
+        )).id();
+        commands.entity(the_game.root_ui_node).add_child(panel);
+    }
+}
+
+/// UI grows and shrinks with the window height.
+fn hud_scale_update(window_query: Query<&Window, With<PrimaryWindow>>, mut ui_scale: ResMut<UiScale>) {
+    let Ok(window) = window_query.get_single() else { return; };
+    let scale = (window.height() / HUD_REFERENCE_HEIGHT).clamp(0.5, 3.0);
+    if ui_scale.0 != scale {
+        ui_scale.0 = scale;
+    }
+}
+
+fn hud_text_update(
+    mut panel_query: Query<(&HudPanel, &mut Text, &mut Style)>,
+    score_query: Query<&Score>,
+    snake_query: Query<(&SnakeModel, &Player)>,
+    progress_query: Query<&LevelProgress>,
+    split_screen: Res<SplitScreen>,
+) {
+    let player_count = score_query.iter().count();
+    let seconds = progress_query.iter().next().map_or(0.0, |progress| progress.seconds);
+    for (panel, mut text, mut style) in &mut panel_query {
+        let score = score_query.iter().find(|score| score.player == panel.player).map_or(0, |score| score.score_num);
+        let snake = snake_query.iter().find(|(_, player)| player.index == panel.player).map(|(snake, _)| snake);
+        let value = hud_text(score, snake, seconds);
+        if text.sections[0].value != value {
+            text.sections[0].value = value;
+        }
+        let Some(pos) = hud_panel_position(panel.player, player_count, split_screen.shared) else { continue; };
+        let (left, top) = (Val::Percent(pos.x as f32), Val::Percent(pos.y as f32));
+        if style.left != left || style.top != top {
+            style.left = left;
+            style.top = top;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food_kind::FoodEffect;
+    use crate::snake_model::{snake_effect_add, snake_model_at};
+
+    #[test]
+    fn time_has_minutes_and_seconds() {
+        assert_eq!(hud_time(0.0), "0:00");
+        assert_eq!(hud_time(65.7), "1:05");
+        assert_eq!(hud_time(600.0), "10:00");
+    }
+
+    #[test]
+    fn panels_are_stacked_with_shared_camera() {
+        assert_eq!(hud_panel_position(1, 2, false), Some(UVec2::new(50, 0)));
+        assert_eq!(hud_panel_position(3, 4, false), Some(UVec2::new(50, 50)));
+        assert_eq!(hud_panel_position(1, 2, true), Some(UVec2::new(0, 25)));
+        assert_eq!(hud_panel_position(3, 4, true), Some(UVec2::new(0, 75)));
+        assert_eq!(hud_panel_position(2, 2, false), None);
+    }
+
+    #[test]
+    fn text_of_dead_player_has_only_score_and_time() {
+        assert_eq!(hud_text(12, None, 3.0), "Score: 12\nLength: -\nSpeed: -\nTime: 0:03");
+    }
+
+    #[test]
+    fn text_lists_active_effects() {
+        let mut snake = snake_model_at(Vec2::ZERO, 0.0);
+        let text = hud_text(0, Some(&snake), 0.0);
+        assert_eq!(text.lines().count(), 4);
+        assert!(text.contains("Length: 5.0"));
+        snake_effect_add(&mut snake, &FoodEffect::Ghost { duration: 2.0 });
+        assert_eq!(hud_text(0, Some(&snake), 0.0).lines().count(), 5);
+    }
+}
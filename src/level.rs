@@ -0,0 +1,318 @@
+use bevy::color::palettes::css::GOLD;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::*;
+use crate::chunk::OpenWorld;
+use crate::food::Score;
+use crate::food_kind::FOOD_KINDS_PATH;
+use crate::food_spawn::FoodSpawnPolicy;
+use crate::obstacle::*;
+use crate::snake_model::SnakeModel;
+use crate::start::TheGame;
+
+pub const LEVEL_DEFAULT_PATH: &str = "assets/levels/classic.ron";
+
+pub struct LevelPlugin;
+
+impl Plugin for LevelPlugin {
+    fn build (&self, app: &mut App) {
+        // level is needed by the startup systems of other plugins, so it is loaded before them
+        let path = std::env::args().nth(1).unwrap_or(LEVEL_DEFAULT_PATH.to_string());
+        app.insert_resource(level_from_file_or_default(&path));
+        app.add_systems(Startup, level_start);
+        app.add_systems(Update, level_win_update);
+    }
+}
+
+/// Position and heading of a new player snake.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct SnakeSpawnPoint {
+    pub pos: Vec2,
+    // counterclockwise from the right side of the screen
+    pub heading_degrees: f32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum WinCondition {
+    Score(i32),
+    // size of the biggest snake
+    Size(f32),
+    SurviveSeconds(f32),
+    // only one of several snakes is alive
+    LastSnakeStanding,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct LevelFood {
+    #[serde(default = "level_food_default_kinds_path")]
+    pub kinds_path: String,
+    // names of kinds spawned in this level, all kinds of the file if empty
+    #[serde(default)]
+    pub kinds: Vec<String>,
+    pub policy: FoodSpawnPolicy,
+    pub target_density: f32,
+    pub spawn_per_second: f32,
+    // food spawns only inside these shapes, in the whole arena if empty
+    #[serde(default)]
+    pub regions: Vec<ArenaShape>,
+}
+
+fn level_food_default_kinds_path() -> String {
+    FOOD_KINDS_PATH.to_string()
+}
+
+/// Everything about the world of one level.
+#[derive(Resource, Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: String,
+    // ignored in the open world
+    pub arena: ArenaShape,
+    // battle royale phases, the arena does not shrink if empty
+    #[serde(default)]
+    pub shrink: Vec<ArenaShrinkPhase>,
+    #[serde(default)]
+    pub obstacles: Vec<ObstacleDefinition>,
+    pub food: LevelFood,
+    pub spawn_points: Vec<SnakeSpawnPoint>,
+    #[serde(default)]
+    pub win: Option<WinCondition>,
+    // endless world without bound instead of the arena
+    #[serde(default)]
+    pub open_world: Option<OpenWorld>,
+}
+
+/// Seconds played since the Start button or the last game over and the win text once the level is complete.
+#[derive(Component)]
+pub struct LevelProgress {
+    pub seconds: f32,
+    // the most snakes alive at the same time
+    max_snakes: usize,
+    // HUD text under the UI root, the time stops while it is shown
+    win_text: Option<Entity>,
+}
+
+/// The world which was hardcoded before levels.
+pub fn level_default() -> Level {
+    Level {
+        name: "classic".to_string(),
+        arena: ArenaShape::Circle { center: Vec2::ZERO, radius: 500.0 },
+        shrink: vec![],
+        obstacles: obstacle_definitions_default(),
+        food: LevelFood {
+            kinds_path: level_food_default_kinds_path(),
+            kinds: vec![],
+            policy: FoodSpawnPolicy::AwayFromSnakes { min_distance: 150.0 },
+            target_density: 0.1,
+            spawn_per_second: 2.0,
+            regions: vec![],
+        },
+        spawn_points: vec![SnakeSpawnPoint { pos: Vec2::ZERO, heading_degrees: 90.0 }],
+        win: None,
+        open_world: None,
+    }
+}
+
+pub fn level_parse(text: &str) -> Result<Level, String> {
+    let level: Level = ron::from_str(text).map_err(|e| e.to_string())?;
+    if level.spawn_points.is_empty() {
+        return Err(format!("level {} has no spawn points", level.name));
+    }
+    Ok(level)
+}
+
+pub fn level_load(path: &str) -> Result<Level, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    level_parse(&text)
+}
+
+/// Loads the level file. If the file is missing or broken the default level is used.
+pub fn level_from_file_or_default(path: &str) -> Level {
+    match level_load(path) {
+        Ok(level) => {
+            println!("Level loaded: {}", level.name);
+            level
+        }
+        Err(error) => {
+            println!("Level is not loaded, default level is used: {error}");
+            level_default()
+        }
+    }
+}
+
+pub fn level_to_string(level: &Level) -> Result<String, String> {
+    ron::ser::to_string_pretty(level, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())
+}
+
+pub fn level_save(level: &Level, path: &str) -> Result<(), String> {
+    let text = level_to_string(level)?;
+    std::fs::write(path, text).map_err(|e| format!("{path}: {e}"))
+}
+
+/// Spawn point for the snake with the given number, points are reused when there are more snakes.
+pub fn level_spawn_point(level: &Level, snake_number: usize) -> &SnakeSpawnPoint {
+    &level.spawn_points[snake_number % level.spawn_points.len()]
+}
+
+pub fn level_win_reached(win: &WinCondition, score: i32, max_size: f32, seconds: f32, alive_snakes: usize, max_snakes: usize) -> bool {
+    match win {
+        WinCondition::Score(target) => score >= *target,
+        WinCondition::Size(target) => max_size >= *target,
+        WinCondition::SurviveSeconds(target) => seconds >= *target && alive_snakes > 0,
+        WinCondition::LastSnakeStanding => max_snakes > 1 && alive_snakes == 1,
+    }
+}
+
+pub fn level_progress_new() -> LevelProgress {
+    LevelProgress { seconds: 0.0, max_snakes: 0, win_text: None }
+}
+
+fn level_start(mut commands: Commands) {
+    commands.spawn(level_progress_new());
+}
+
+/// New game after game over: the time starts again and the win text is removed.
+pub fn level_progress_reset(commands: &mut Commands, the_game: &TheGame, progress: &mut LevelProgress) {
+    if let Some(win_text) = progress.win_text {
+        commands.entity(the_game.root_ui_node).remove_children(&[win_text]);
+        commands.entity(win_text).despawn_recursive();
+    }
+    *progress = level_progress_new();
+}
+
+/// Counts the played time and shows the win text in the HUD once the level win condition is reached.
+#[allow(clippy::too_many_arguments)]
+fn level_win_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    mut progress_query: Query<&mut LevelProgress>,
+    score_query: Query<&Score>,
+    snake_query: Query<&SnakeModel>,
+    the_game_query: Query<&TheGame>,
+    time: Res<Time>,
+) {
+    // the game runs behind the start menu, but it is not played yet
+    let Ok(the_game) = the_game_query.get_single() else { return; };
+    if the_game.start_button_entity.is_some() {
+        return;
+    }
+    for mut progress in &mut progress_query {
+        if progress.win_text.is_some() {
+            continue;
+        }
+        progress.seconds += time.delta_seconds();
+        let Some(win) = &level.win else { continue; };
+        let alive_snakes = snake_query.iter().count();
+        progress.max_snakes = usize::max(progress.max_snakes, alive_snakes);
+        let score = score_query.iter().map(|score| score.score_num).max().unwrap_or(0);
+        let max_size = snake_query.iter().map(|snake| snake.size).fold(0.0, f32::max);
+        if level_win_reached(win, score, max_size, progress.seconds, alive_snakes, progress.max_snakes) {
+            let text_style = TextStyle {
+                font: asset_server.load("MovistarTextRegular.ttf"),
+                font_size: 60.0,
+                color: GOLD.into(),
+            };
+            let win_text = commands.spawn(
+                TextBundle::from_section(format!("{} complete", level.name), text_style).with_text_justify(JustifyText::Center)
+            ).id();
+            commands.entity(the_game.root_ui_node).add_child(win_text);
+            progress.win_text = Some(win_text);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_levels_are_valid() {
+        let mut count = 0;
+        for entry in std::fs::read_dir("assets/levels").unwrap() {
+            let path = entry.unwrap().path();
+            let level = level_load(path.to_str().unwrap()).unwrap();
+            assert!(!level.name.is_empty());
+            count += 1;
+        }
+        assert!(count >= 2);
+    }
+
+    #[test]
+    fn default_path_is_bundled() {
+        assert!(level_load(LEVEL_DEFAULT_PATH).is_ok());
+    }
+
+    #[test]
+    fn parse_minimal_level() {
+        let text = r#"(
+            name: "test",
+            arena: Rect(center: (0.0, 0.0), half_size: (100.0, 50.0)),
+            food: (policy: Uniform, target_density: 0.5, spawn_per_second: 1.0),
+            spawn_points: [(pos: (10.0, -20.0), heading_degrees: 0.0)],
+        )"#;
+        let level = level_parse(text).unwrap();
+        assert_eq!(level.arena, ArenaShape::Rect { center: Vec2::ZERO, half_size: Vec2::new(100.0, 50.0) });
+        assert_eq!(level.food.kinds_path, FOOD_KINDS_PATH);
+        assert!(level.obstacles.is_empty());
+        assert_eq!(level.win, None);
+        assert_eq!(level_spawn_point(&level, 3).pos, Vec2::new(10.0, -20.0));
+    }
+
+    #[test]
+    fn parse_obstacles_and_win() {
+        let text = r#"(
+            name: "test",
+            arena: Circle(center: (0.0, 0.0), radius: 300.0),
+            shrink: [(wait_seconds: 5.0, shrink_seconds: 5.0, scale: 0.5)],
+            obstacles: [
+                (shape: Segment(a: (0.0, 0.0), b: (10.0, 0.0), thickness: 4.0), contact: Block),
+                (shape: Circle(center: (50.0, 50.0), radius: 10.0), contact: Kill, sprite: Some("Test.png")),
+            ],
+            food: (kinds: ["berry"], policy: Clusters(count: 2, radius: 40.0), target_density: 0.5, spawn_per_second: 1.0,
+                regions: [Circle(center: (0.0, 0.0), radius: 100.0)]),
+            spawn_points: [(pos: (0.0, 0.0), heading_degrees: 90.0)],
+            win: Some(Score(50)),
+        )"#;
+        let level = level_parse(text).unwrap();
+        assert_eq!(level.obstacles.len(), 2);
+        assert_eq!(level.obstacles[0].sprite, None);
+        assert_eq!(level.obstacles[1].contact, ObstacleContact::Kill);
+        assert_eq!(level.shrink.len(), 1);
+        assert_eq!(level.food.kinds, vec!["berry".to_string()]);
+        assert_eq!(level.win, Some(WinCondition::Score(50)));
+        assert_eq!(level.open_world, None);
+    }
+
+    #[test]
+    fn saved_level_is_loaded_back() {
+        let mut level = level_default();
+        level.food.regions.push(ArenaShape::Polygon { points: vec![Vec2::ZERO, Vec2::X, Vec2::Y] });
+        level.win = Some(WinCondition::LastSnakeStanding);
+        let text = level_to_string(&level).unwrap();
+        assert_eq!(level_parse(&text).unwrap(), level);
+    }
+
+    #[test]
+    fn level_without_spawn_points_is_rejected() {
+        let text = r#"(
+            name: "empty",
+            arena: Circle(center: (0.0, 0.0), radius: 300.0),
+            food: (policy: Uniform, target_density: 0.5, spawn_per_second: 1.0),
+            spawn_points: [],
+        )"#;
+        assert!(level_parse(text).is_err());
+    }
+
+    #[test]
+    fn win_conditions() {
+        assert!(level_win_reached(&WinCondition::Score(10), 10, 0.0, 0.0, 1, 1));
+        assert!(!level_win_reached(&WinCondition::Score(10), 9, 0.0, 0.0, 1, 1));
+        assert!(level_win_reached(&WinCondition::Size(20.0), 0, 25.0, 0.0, 1, 1));
+        assert!(level_win_reached(&WinCondition::SurviveSeconds(60.0), 0, 0.0, 61.0, 1, 1));
+        assert!(!level_win_reached(&WinCondition::SurviveSeconds(60.0), 0, 0.0, 61.0, 0, 1));
+        // a single snake is not the last one standing
+        assert!(!level_win_reached(&WinCondition::LastSnakeStanding, 0, 0.0, 0.0, 1, 1));
+        assert!(level_win_reached(&WinCondition::LastSnakeStanding, 0, 0.0, 0.0, 1, 3));
+    }
+}
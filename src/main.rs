@@ -1,12 +1,27 @@
 mod start;
+mod arena;
+mod camera;
+mod chunk;
 mod grid;
+mod highscore;
+mod hud;
+mod level;
+mod minimap;
 mod sprite;
 mod snake_extension;
 mod snake_model;
 mod food;
+mod food_kind;
+mod food_spawn;
+mod food_steering;
+mod obstacle;
+mod player;
+mod editor;
+mod spatial_hash;
 mod foo;
 mod snake_model_tests;
 mod creature_body_evolution;
+mod body_profile;
 mod trace_position_calculator;
 
 use bevy::{
@@ -18,17 +33,22 @@ fn main() {
     App::new()
         .add_plugins((DefaultPlugins, Wireframe2dPlugin))
 
+        .add_plugins(crate::level::LevelPlugin)
+        .add_plugins(crate::highscore::HighScorePlugin)
+        .add_plugins(crate::camera::CameraPlugin)
+        .add_plugins(crate::player::PlayerPlugin)
         .add_plugins(crate::start::StartPlugin)
         .add_plugins(crate::grid::VisualDiagnosticPlugin)
         .add_plugins(crate::snake_extension::SnakePlugin)
+        .add_plugins(crate::spatial_hash::SpatialHashPlugin)
+        .add_plugins(crate::arena::ArenaPlugin)
+        .add_plugins(crate::obstacle::ObstaclePlugin)
         .add_plugins(crate::food::FoodPlugin)
+        .add_plugins(crate::chunk::ChunkPlugin)
+        .add_plugins(crate::minimap::MinimapPlugin)
+        .add_plugins(crate::hud::HudPlugin)
+        .add_plugins(crate::editor::EditorPlugin)
         //.add_plugins(crate::sprite::SpritePlugin)
 
-        .add_systems(Startup, setup)
-
         .run();
-}
-
-fn setup(mut commands: Commands) {
-    commands.spawn(Camera2dBundle::default());
 }
\ No newline at end of file
@@ -0,0 +1,204 @@
+use bevy::color::palettes::css::{GREY, WHITE};
+use bevy::prelude::*;
+use bevy::render::view::RenderLayers;
+
+use crate::arena::*;
+use crate::camera::{camera_follow_update, CameraFollow};
+use crate::food::Food;
+use crate::highscore::name_entry_closed;
+use crate::player::Player;
+use crate::snake_model::SnakeModel;
+
+// only the camera of the first player renders this layer, so the minimap is not seen in other viewports
+pub const MINIMAP_RENDER_LAYER: usize = 1;
+// side of the minimap square in window pixels
+const MINIMAP_SIZE: f32 = 200.0;
+const MINIMAP_MARGIN: f32 = 10.0;
+// area shown around the camera in the open world
+const MINIMAP_OPEN_WORLD_EXTENT: f32 = 4000.0;
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build (&self, app: &mut App) {
+        app.insert_gizmo_config(MinimapGizmos, GizmoConfig {
+            line_width: 1.0,
+            render_layers: RenderLayers::layer(MINIMAP_RENDER_LAYER),
+            ..default()
+        });
+        app.add_systems(Startup, minimap_start);
+        app.add_systems(Update, (minimap_toggle.run_if(name_entry_closed), draw_minimap.after(camera_follow_update)));
+    }
+}
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MinimapGizmos;
+
+/// Minimap in the top right corner of the first player viewport. KeyM shows and hides it.
+#[derive(Component)]
+pub struct Minimap {
+    enabled: bool,
+}
+
+/// World rectangle shown by the minimap and where it is drawn.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MinimapMapping {
+    pub world_center: Vec2,
+    pub world_half_size: Vec2,
+    pub map_center: Vec2,
+    // map units for one world unit
+    pub scale: f32,
+}
+
+fn minimap_start(mut commands: Commands) {
+    commands.spawn(Minimap { enabled: true });
+}
+
+/// Fits the world rectangle into the square map of the given half size, keeping the aspect ratio.
+pub fn minimap_mapping(world_min: Vec2, world_max: Vec2, map_center: Vec2, map_half_size: f32) -> MinimapMapping {
+    let world_half_size = ((world_max - world_min) / 2.0).max(Vec2::splat(1.0));
+    MinimapMapping {
+        world_center: (world_min + world_max) / 2.0,
+        world_half_size,
+        map_center,
+        scale: map_half_size / world_half_size.max_element(),
+    }
+}
+
+pub fn minimap_contains(mapping: &MinimapMapping, p: Vec2) -> bool {
+    let offset = (p - mapping.world_center).abs();
+    offset.x <= mapping.world_half_size.x && offset.y <= mapping.world_half_size.y
+}
+
+pub fn minimap_point(mapping: &MinimapMapping, p: Vec2) -> Vec2 {
+    mapping.map_center + (p - mapping.world_center) * mapping.scale
+}
+
+/// Arena shape moved and scaled into the map.
+pub fn minimap_shape(mapping: &MinimapMapping, shape: &ArenaShape) -> ArenaShape {
+    match shape {
+        ArenaShape::Circle { center, radius } => ArenaShape::Circle { center: minimap_point(mapping, *center), radius: radius * mapping.scale },
+        ArenaShape::Rect { center, half_size } => ArenaShape::Rect { center: minimap_point(mapping, *center), half_size: *half_size * mapping.scale },
+        ArenaShape::RoundedRect { center, half_size, corner_radius } => ArenaShape::RoundedRect {
+            center: minimap_point(mapping, *center),
+            half_size: *half_size * mapping.scale,
+            corner_radius: corner_radius * mapping.scale,
+        },
+        ArenaShape::Polygon { points } => ArenaShape::Polygon { points: points.iter().map(|point| minimap_point(mapping, *point)).collect() },
+    }
+}
+
+/// Map parts of the polyline which are inside the map. The line is split where it leaves the map, so no line is drawn
+/// across the gap.
+pub fn minimap_polylines(mapping: &MinimapMapping, points: impl IntoIterator<Item = Vec2>) -> Vec<Vec<Vec2>> {
+    let mut polylines: Vec<Vec<Vec2>> = vec![Vec::new()];
+    for p in points {
+        if minimap_contains(mapping, p) {
+            polylines.last_mut().unwrap().push(minimap_point(mapping, p));
+        }
+        else if !polylines.last().unwrap().is_empty() {
+            polylines.push(Vec::new());
+        }
+    }
+    polylines.retain(|polyline| !polyline.is_empty());
+    polylines
+}
+
+pub fn minimap_player_color(player: usize) -> Color {
+    Color::hsl(120.0 + 90.0 * player as f32, 0.9, 0.6)
+}
+
+fn minimap_toggle(mut minimap_query: Query<&mut Minimap>, keyboard_input: Res<ButtonInput<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::KeyM) {
+        for mut minimap in &mut minimap_query {
+            minimap.enabled = !minimap.enabled;
+        }
+    }
+}
+
+fn draw_minimap(
+    mut gizmos: Gizmos<MinimapGizmos>,
+    minimap_query: Query<&Minimap>,
+    camera_query: Query<(&Camera, &Transform, &OrthographicProjection, &CameraFollow)>,
+    bound_query: Query<(&Bound, Option<&ArenaShrink>)>,
+    food_query: Query<&Food>,
+    snake_query: Query<(&SnakeModel, &Player)>,
+) {
+    let Ok(minimap) = minimap_query.get_single() else { return; };
+    if !minimap.enabled {
+        return;
+    }
+    let Some((camera, camera_transform, projection, _)) = camera_query.iter().find(|(.., follow)| follow.player == 0) else { return; };
+    let Some(view_size) = camera.logical_viewport_size() else { return; };
+
+    // the map is drawn in world units, so it is scaled with the camera zoom to keep its size on the screen
+    let camera_pos = camera_transform.translation.truncate();
+    let pixel = projection.scale;
+    let map_half_size = MINIMAP_SIZE / 2.0 * pixel;
+    let map_center = camera_pos + (view_size / 2.0 - Vec2::splat(MINIMAP_SIZE / 2.0 + MINIMAP_MARGIN)) * pixel;
+    let (world_min, world_max) = match bound_query.iter().next() {
+        // the shrinking arena stays in the same place on the map
+        Some((bound, shrink)) => arena_bounding_box(shrink.map_or(&bound.shape, |shrink| &shrink.start_shape)),
+        None => (camera_pos - Vec2::splat(MINIMAP_OPEN_WORLD_EXTENT / 2.0), camera_pos + Vec2::splat(MINIMAP_OPEN_WORLD_EXTENT / 2.0)),
+    };
+    let mapping = minimap_mapping(world_min, world_max, map_center, map_half_size);
+
+    gizmos.rect_2d(map_center, 0.0, Vec2::splat(map_half_size * 2.0), GREY);
+    for (bound, _) in &bound_query {
+        arena_draw(&mut gizmos, &minimap_shape(&mapping, &bound.shape), WHITE.into());
+    }
+    for food in food_query.iter().filter(|food| minimap_contains(&mapping, food.pos)) {
+        gizmos.circle_2d(minimap_point(&mapping, food.pos), 1.5 * pixel, food.color).resolution(6);
+    }
+    for (snake, player) in &snake_query {
+        let color = minimap_player_color(player.index);
+        let body = std::iter::once(snake.head_pos).chain(snake.trace.iter().map(|item| item.pos));
+        for polyline in minimap_polylines(&mapping, body) {
+            gizmos.linestrip_2d(polyline, color);
+        }
+        if minimap_contains(&mapping, snake.head_pos) {
+            gizmos.circle_2d(minimap_point(&mapping, snake.head_pos), 3.0 * pixel, color);
+        }
+    }
+    // what every active camera sees
+    for (camera, transform, projection, follow) in &camera_query {
+        let Some(size) = camera.logical_viewport_size().filter(|_| camera.is_active) else { continue; };
+        let half_size = size / 2.0 * projection.scale;
+        let center = transform.translation.truncate();
+        let min = minimap_point(&mapping, (center - half_size).clamp(world_min, world_max));
+        let max = minimap_point(&mapping, (center + half_size).clamp(world_min, world_max));
+        gizmos.rect_2d((min + max) / 2.0, 0.0, max - min, minimap_player_color(follow.player));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mapping_keeps_aspect_ratio() {
+        let mapping = minimap_mapping(Vec2::new(-1000.0, -500.0), Vec2::new(1000.0, 500.0), Vec2::new(300.0, 200.0), 100.0);
+        assert_eq!(mapping.scale, 0.1);
+        assert_eq!(minimap_point(&mapping, Vec2::ZERO), Vec2::new(300.0, 200.0));
+        assert_eq!(minimap_point(&mapping, Vec2::new(1000.0, 500.0)), Vec2::new(400.0, 250.0));
+        assert!(minimap_contains(&mapping, Vec2::new(-1000.0, 500.0)));
+        assert!(!minimap_contains(&mapping, Vec2::new(0.0, 600.0)));
+    }
+
+    #[test]
+    fn polyline_is_split_outside_map() {
+        let mapping = minimap_mapping(Vec2::splat(-100.0), Vec2::splat(100.0), Vec2::ZERO, 10.0);
+        let points = [Vec2::new(-50.0, 0.0), Vec2::new(0.0, 0.0), Vec2::new(200.0, 0.0), Vec2::new(300.0, 0.0), Vec2::new(50.0, 0.0), Vec2::new(300.0, 0.0)];
+        assert_eq!(minimap_polylines(&mapping, points), vec![vec![Vec2::new(-5.0, 0.0), Vec2::ZERO], vec![Vec2::new(5.0, 0.0)]]);
+        assert!(minimap_polylines(&mapping, [Vec2::splat(500.0)]).is_empty());
+    }
+
+    #[test]
+    fn shapes_are_scaled_into_map() {
+        let mapping = minimap_mapping(Vec2::splat(-500.0), Vec2::splat(500.0), Vec2::new(50.0, 0.0), 50.0);
+        let circle = minimap_shape(&mapping, &ArenaShape::Circle { center: Vec2::new(100.0, 0.0), radius: 500.0 });
+        assert_eq!(circle, ArenaShape::Circle { center: Vec2::new(60.0, 0.0), radius: 50.0 });
+        let polygon = minimap_shape(&mapping, &ArenaShape::Polygon { points: vec![Vec2::ZERO, Vec2::new(500.0, 500.0)] });
+        assert_eq!(polygon, ArenaShape::Polygon { points: vec![Vec2::new(50.0, 0.0), Vec2::new(100.0, 50.0)] });
+    }
+}
@@ -0,0 +1,264 @@
+use bevy::color::palettes::css::{ORANGE_RED, WHITE};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::arena::*;
+use crate::level::Level;
+
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build (&self, app: &mut App) {
+        app.add_systems(Startup, obstacle_start);
+        app.add_systems(Update, draw_obstacles);
+    }
+}
+
+/// Shape of an obstacle. Polygon may be concave.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub enum ObstacleShape {
+    Circle { center: Vec2, radius: f32 },
+    // wall from a to b
+    Segment { a: Vec2, b: Vec2, thickness: f32 },
+    Polygon { points: Vec<Vec2> },
+}
+
+/// What happens to a snake whose head touches the obstacle. Food always bounces off.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq)]
+pub enum ObstacleContact {
+    Block,
+    Kill,
+}
+
+#[derive(Component)]
+pub struct Obstacle {
+    pub shape: ObstacleShape,
+    pub contact: ObstacleContact,
+}
+
+/// Obstacle description used to spawn it. Without sprite only the gizmo outline is drawn.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ObstacleDefinition {
+    pub shape: ObstacleShape,
+    pub contact: ObstacleContact,
+    #[serde(default)]
+    pub sprite: Option<String>,
+}
+
+pub fn obstacle_definitions_default() -> Vec<ObstacleDefinition> {
+    vec![
+        ObstacleDefinition {
+            shape: ObstacleShape::Circle { center: Vec2::new(250.0, 150.0), radius: 40.0 },
+            contact: ObstacleContact::Kill,
+            sprite: None,
+        },
+        ObstacleDefinition {
+            shape: ObstacleShape::Segment { a: Vec2::new(-300.0, -100.0), b: Vec2::new(-150.0, -250.0), thickness: 16.0 },
+            contact: ObstacleContact::Block,
+            sprite: None,
+        },
+        ObstacleDefinition {
+            shape: ObstacleShape::Polygon { points: vec![Vec2::new(-250.0, 200.0), Vec2::new(-150.0, 200.0), Vec2::new(-200.0, 280.0)] },
+            contact: ObstacleContact::Block,
+            sprite: None,
+        },
+    ]
+}
+
+fn obstacle_start(mut commands: Commands, asset_server: Res<AssetServer>, level: Res<Level>) {
+    for definition in level.obstacles.iter() {
+        obstacle_spawn(&mut commands, &asset_server, definition.clone());
+    }
+}
+
+/// Sprite is stretched over the box around the shape, segment sprite is rotated along the segment.
+pub fn obstacle_spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, definition: ObstacleDefinition) -> Entity {
+    let obstacle = Obstacle { shape: definition.shape, contact: definition.contact };
+    let Some(sprite) = definition.sprite else {
+        return commands.spawn(obstacle).id();
+    };
+    let (center, size, rotation) = match &obstacle.shape {
+        ObstacleShape::Circle { center, radius } => (*center, Vec2::splat(radius * 2.0), 0.0),
+        ObstacleShape::Segment { a, b, thickness } => ((*a + *b) / 2.0, Vec2::new(a.distance(*b) + thickness, *thickness), (*b - *a).to_angle()),
+        ObstacleShape::Polygon { points } => {
+            let (min, max) = arena_bounding_box(&ArenaShape::Polygon { points: points.clone() });
+            ((min + max) / 2.0, max - min, 0.0)
+        }
+    };
+    commands.spawn((
+        SpriteBundle {
+            texture: asset_server.load(sprite),
+            sprite: Sprite { custom_size: Some(size), ..default() },
+            transform: Transform::from_xyz(center.x, center.y, 0.0).with_rotation(Quat::from_rotation_z(rotation)),
+            ..default()
+        },
+        obstacle,
+    )).id()
+}
+
+/// Replaces all obstacle entities with new ones from the definitions.
+pub fn obstacles_rebuild(commands: &mut Commands, asset_server: &Res<AssetServer>, obstacle_query: &Query<Entity, With<Obstacle>>, definitions: &[ObstacleDefinition]) {
+    for entity in obstacle_query {
+        commands.entity(entity).despawn();
+    }
+    for definition in definitions {
+        obstacle_spawn(commands, asset_server, definition.clone());
+    }
+}
+
+/// Obstacle moved by delta.
+pub fn obstacle_translated(shape: &ObstacleShape, delta: Vec2) -> ObstacleShape {
+    match shape {
+        ObstacleShape::Circle { center, radius } => ObstacleShape::Circle { center: *center + delta, radius: *radius },
+        ObstacleShape::Segment { a, b, thickness } => ObstacleShape::Segment { a: *a + delta, b: *b + delta, thickness: *thickness },
+        ObstacleShape::Polygon { points } => ObstacleShape::Polygon { points: points.iter().map(|point| *point + delta).collect() },
+    }
+}
+
+/// Closest point of the obstacle outline and normal looking out of the obstacle there.
+fn obstacle_closest(shape: &ObstacleShape, p: Vec2) -> (Vec2, Vec2) {
+    match shape {
+        ObstacleShape::Circle { center, radius } => {
+            let normal = (p - *center).try_normalize().unwrap_or(Vec2::X);
+            (*center + normal * *radius, normal)
+        }
+        ObstacleShape::Segment { a, b, thickness } => {
+            let on_segment = segment_closest_point(*a, *b, p);
+            let normal = (p - on_segment).try_normalize().unwrap_or((*b - *a).perp().normalize_or_zero());
+            (on_segment + normal * *thickness / 2.0, normal)
+        }
+        ObstacleShape::Polygon { points } => polygon_closest(points, p),
+    }
+}
+
+/// Distance from the obstacle outline, negative inside the obstacle.
+pub fn obstacle_signed_distance(shape: &ObstacleShape, p: Vec2) -> f32 {
+    let (closest, normal) = obstacle_closest(shape, p);
+    match shape {
+        ObstacleShape::Polygon { points } => {
+            let distance = closest.distance(p);
+            if polygon_contains(points, p) { -distance } else { distance }
+        }
+        _ => (p - closest).dot(normal),
+    }
+}
+
+pub fn obstacle_overlaps(shape: &ObstacleShape, pos: Vec2, radius: f32) -> bool {
+    obstacle_signed_distance(shape, pos) < radius
+}
+
+/// Circle touching the obstacle is pushed out and its velocity is reflected if it moves into the obstacle.
+/// Returns new position and velocity.
+pub fn obstacle_bounce(shape: &ObstacleShape, pos: Vec2, radius: f32, velocity: Vec2) -> (Vec2, Vec2) {
+    if !obstacle_overlaps(shape, pos, radius) {
+        return (pos, velocity);
+    }
+    let (closest, normal) = obstacle_closest(shape, pos);
+    let velocity = if velocity.dot(normal) < 0.0 { arena_reflect(velocity, normal) } else { velocity };
+    (closest + normal * radius, velocity)
+}
+
+/// Circle around the whole obstacle, used to put it into the spatial hash.
+pub fn obstacle_bounding_circle(shape: &ObstacleShape) -> (Vec2, f32) {
+    match shape {
+        ObstacleShape::Circle { center, radius } => (*center, *radius),
+        ObstacleShape::Segment { a, b, thickness } => ((*a + *b) / 2.0, a.distance(*b) / 2.0 + thickness / 2.0),
+        ObstacleShape::Polygon { points } => {
+            let (min, max) = arena_bounding_box(&ArenaShape::Polygon { points: points.clone() });
+            ((min + max) / 2.0, min.distance(max) / 2.0)
+        }
+    }
+}
+
+fn draw_obstacles(mut gizmos: Gizmos, obstacle_query: Query<&Obstacle>) {
+    for obstacle in &obstacle_query {
+        let color = match obstacle.contact {
+            ObstacleContact::Block => WHITE,
+            ObstacleContact::Kill => ORANGE_RED,
+        };
+        match &obstacle.shape {
+            ObstacleShape::Circle { center, radius } => {
+                gizmos.circle_2d(*center, *radius, color);
+            }
+            ObstacleShape::Segment { a, b, thickness } => {
+                let side = (*b - *a).perp().normalize_or_zero() * *thickness / 2.0;
+                gizmos.linestrip_2d([*a + side, *b + side, *b - side, *a - side, *a + side], color);
+            }
+            ObstacleShape::Polygon { points } => {
+                gizmos.linestrip_2d(points.iter().chain(points.first()).copied(), color);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_vec2_eq(a: Vec2, b: Vec2) {
+        assert!(a.distance(b) < 0.001, "{a} != {b}");
+    }
+
+    #[test]
+    fn circle_obstacle_distance() {
+        let shape = ObstacleShape::Circle { center: Vec2::new(10.0, 0.0), radius: 5.0 };
+        assert!((obstacle_signed_distance(&shape, Vec2::new(20.0, 0.0)) - 5.0).abs() < 0.001);
+        assert!(obstacle_signed_distance(&shape, Vec2::new(11.0, 0.0)) < 0.0);
+        assert!(obstacle_overlaps(&shape, Vec2::new(20.0, 0.0), 6.0));
+        assert!(!obstacle_overlaps(&shape, Vec2::new(20.0, 0.0), 4.0));
+    }
+
+    #[test]
+    fn segment_obstacle_has_thickness() {
+        let shape = ObstacleShape::Segment { a: Vec2::ZERO, b: Vec2::new(100.0, 0.0), thickness: 10.0 };
+        assert!((obstacle_signed_distance(&shape, Vec2::new(50.0, 20.0)) - 15.0).abs() < 0.001);
+        assert!((obstacle_signed_distance(&shape, Vec2::new(110.0, 0.0)) - 5.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn polygon_obstacle_inside_is_negative() {
+        let shape = ObstacleShape::Polygon { points: vec![Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0)] };
+        assert!((obstacle_signed_distance(&shape, Vec2::new(5.0, 8.0)) + 2.0).abs() < 0.001);
+        assert!((obstacle_signed_distance(&shape, Vec2::new(5.0, 13.0)) - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn food_bounces_off_segment() {
+        let shape = ObstacleShape::Segment { a: Vec2::new(-50.0, 0.0), b: Vec2::new(50.0, 0.0), thickness: 10.0 };
+        let (pos, velocity) = obstacle_bounce(&shape, Vec2::new(0.0, 8.0), 5.0, Vec2::new(2.0, -3.0));
+        assert_vec2_eq(pos, Vec2::new(0.0, 10.0));
+        assert_vec2_eq(velocity, Vec2::new(2.0, 3.0));
+    }
+
+    #[test]
+    fn food_bounces_off_polygon() {
+        let shape = ObstacleShape::Polygon { points: vec![Vec2::ZERO, Vec2::new(10.0, 0.0), Vec2::new(10.0, 10.0), Vec2::new(0.0, 10.0)] };
+        let (pos, velocity) = obstacle_bounce(&shape, Vec2::new(5.0, 12.0), 5.0, Vec2::new(0.0, -3.0));
+        assert!(obstacle_signed_distance(&shape, pos) >= 5.0 - 0.001);
+        assert_vec2_eq(pos, Vec2::new(5.0, 15.0));
+        assert_vec2_eq(velocity, Vec2::new(0.0, 3.0));
+    }
+
+    #[test]
+    fn bounce_keeps_velocity_moving_away() {
+        let shape = ObstacleShape::Circle { center: Vec2::ZERO, radius: 10.0 };
+        let (pos, velocity) = obstacle_bounce(&shape, Vec2::new(12.0, 0.0), 5.0, Vec2::new(1.0, 1.0));
+        assert_vec2_eq(pos, Vec2::new(15.0, 0.0));
+        assert_vec2_eq(velocity, Vec2::new(1.0, 1.0));
+    }
+
+    #[test]
+    fn bounding_circle_contains_shape() {
+        let shapes = [
+            ObstacleShape::Segment { a: Vec2::new(-30.0, 10.0), b: Vec2::new(40.0, -20.0), thickness: 6.0 },
+            ObstacleShape::Polygon { points: vec![Vec2::new(0.0, 0.0), Vec2::new(50.0, 10.0), Vec2::new(20.0, 60.0)] },
+        ];
+        for shape in shapes.iter() {
+            let (center, radius) = obstacle_bounding_circle(shape);
+            for angle in 0..36 {
+                let far = center + Vec2::from_angle(angle as f32 * 10.0_f32.to_radians()) * (radius + 0.01);
+                assert!(obstacle_signed_distance(shape, far) > 0.0);
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::prelude::*;
+
+use crate::camera::camera_spawn;
+use crate::food::score_spawn;
+use crate::highscore::name_entry_closed;
+use crate::level::*;
+use crate::snake_extension::snake_spawn_player;
+
+pub const PLAYERS_MAX: usize = 4;
+
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build (&self, app: &mut App) {
+        app.insert_resource(LocalPlayers { count: 0 });
+        app.add_systems(Startup, player_start);
+        app.add_systems(Update, player_join_update.run_if(name_entry_closed));
+    }
+}
+
+/// Snake controlled by a local player.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Player {
+    pub index: usize,
+}
+
+/// Number of local players sharing the keyboard.
+#[derive(Resource)]
+pub struct LocalPlayers {
+    pub count: usize,
+}
+
+/// Keys of one player.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PlayerControls {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub boost: KeyCode,
+}
+
+pub fn player_controls(index: usize) -> PlayerControls {
+    match index {
+        0 => PlayerControls { forward: KeyCode::ArrowUp, backward: KeyCode::ArrowDown, left: KeyCode::ArrowLeft, right: KeyCode::ArrowRight, boost: KeyCode::Space },
+        1 => PlayerControls { forward: KeyCode::KeyW, backward: KeyCode::KeyS, left: KeyCode::KeyA, right: KeyCode::KeyD, boost: KeyCode::ShiftLeft },
+        2 => PlayerControls { forward: KeyCode::KeyI, backward: KeyCode::KeyK, left: KeyCode::KeyJ, right: KeyCode::KeyL, boost: KeyCode::ShiftRight },
+        _ => PlayerControls { forward: KeyCode::Numpad8, backward: KeyCode::Numpad5, left: KeyCode::Numpad4, right: KeyCode::Numpad6, boost: KeyCode::Numpad0 },
+    }
+}
+
+fn player_start(mut commands: Commands, asset_server: Res<AssetServer>, level: Res<Level>, mut players: ResMut<LocalPlayers>) {
+    player_join(&mut commands, &asset_server, &level, &mut players);
+}
+
+/// New player gets a snake at the next spawn point, a camera and a score.
+fn player_join(commands: &mut Commands, asset_server: &Res<AssetServer>, level: &Level, players: &mut LocalPlayers) {
+    let player = Player { index: players.count };
+    players.count += 1;
+    snake_spawn_player(commands, asset_server, level, player);
+    camera_spawn(commands, player);
+    score_spawn(commands, player);
+}
+
+/// KeyN adds a local player until there are PLAYERS_MAX of them.
+fn player_join_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    mut players: ResMut<LocalPlayers>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyN) && players.count < PLAYERS_MAX {
+        player_join(&mut commands, &asset_server, &level, &mut players);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn players_do_not_share_keys() {
+        let mut keys: Vec<KeyCode> = Vec::new();
+        for index in 0..PLAYERS_MAX {
+            let controls = player_controls(index);
+            keys.extend([controls.forward, controls.backward, controls.left, controls.right, controls.boost]);
+        }
+        for (i, key) in keys.iter().enumerate() {
+            assert!(!keys[i + 1..].contains(key), "{key:?} is used twice");
+        }
+    }
+}
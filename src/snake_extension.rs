@@ -8,10 +8,18 @@ use std::f32::*;
 use bevy::color::palettes::css::*;
 use bevy::input::ButtonInput;
 
+use crate::arena::*;
+use crate::body_profile::*;
 use crate::creature_body_evolution::*;
-use crate::foo::*;
+use crate::food::{food_pellet_spawn, food_spawn_on_death, Score};
+use crate::food_spawn::food_spawn_positions_along_body;
+use crate::highscore::*;
 use crate::grid::*;
+use crate::level::*;
+use crate::obstacle::*;
+use crate::player::*;
 use crate::snake_model::*;
+use crate::spatial_hash::*;
 use crate::trace_position_calculator::*;
 use crate::start::*;
 
@@ -19,33 +27,151 @@ pub struct SnakePlugin;
 
 impl Plugin for SnakePlugin {
     fn build (&self, app: &mut App) {
-        app.add_systems(Startup, snake_start);
         app.add_systems(Update, snake_update);
+        app.add_systems(Update, (snake_collision_update, snake_safe_zone_update, snake_death_update).chain().after(spatial_hash_rebuild));
     }
 }
 
+/// Snake which is turned into food and despawned by snake_death_update.
+#[derive(Component)]
+pub struct SnakeDead;
 
-fn snake_start (mut commands: Commands,  asset_server: Res<AssetServer>) {
-    for mut snake in snake_head_new_list() {
-        let list = spine_from_size(&mut commands, &asset_server, &mut snake);
-        snake.body = list;
-        commands.spawn(snake);
+
+/// Snake of the player at the spawn point with the player number.
+pub fn snake_spawn_player(commands: &mut Commands, asset_server: &Res<AssetServer>, level: &Level, player: Player) {
+    snake_spawn(commands, asset_server, snake_from_spawn_point(level_spawn_point(level, player.index)), player);
+}
+
+fn snake_from_spawn_point(spawn_point: &SnakeSpawnPoint) -> SnakeModel {
+    snake_model_at(spawn_point.pos, spawn_point.heading_degrees.to_radians())
+}
+
+fn snake_spawn(commands: &mut Commands, asset_server: &Res<AssetServer>, mut snake: SnakeModel, player: Player) {
+    let list = spine_from_size(commands, asset_server, &mut snake);
+    snake.body = list;
+    spine_distances_update(&mut snake);
+    spine_positions_update(&mut snake);
+    commands.spawn((snake, player));
+}
+
+/// Snake dies when its head touches its own body, the body of another snake or a killing obstacle.
+/// Blocking obstacles push the head out.
+fn snake_collision_update(
+    mut commands: Commands,
+    mut snake_query: Query<(Entity, &mut SnakeModel), Without<SnakeDead>>,
+    obstacle_query: Query<&Obstacle>,
+    spatial_hash: Res<SpatialHash>,
+) {
+    for (snake_entity, mut snake) in &mut snake_query {
+        let mut dead = false;
+        for entry in spatial_hash_query(&spatial_hash, snake.head_pos, snake.head_radius) {
+            match entry.item {
+                SpatialItem::SnakeNode { snake: other, node_index } => {
+                    if other != snake_entity || snake_self_collision_kills(&snake, node_index) {
+                        dead = true;
+                    }
+                }
+                SpatialItem::Obstacle(obstacle_entity) => {
+                    let Ok(obstacle) = obstacle_query.get(obstacle_entity) else { continue; };
+                    if !obstacle_overlaps(&obstacle.shape, snake.head_pos, snake.head_radius) {
+                        continue;
+                    }
+                    match obstacle.contact {
+                        ObstacleContact::Kill => dead = true,
+                        ObstacleContact::Block => {
+                            snake.head_pos = obstacle_bounce(&obstacle.shape, snake.head_pos, snake.head_radius, Vec2::ZERO).0;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        if dead {
+            commands.entity(snake_entity).insert(SnakeDead);
+        }
+    }
+}
+
+/// In battle royale mode snakes outside the safe zone lose size and die when it is over.
+fn snake_safe_zone_update(
+    mut commands: Commands,
+    mut snake_query: Query<(Entity, &mut SnakeModel), Without<SnakeDead>>,
+    bound_query: Query<(&Bound, &ArenaShrink)>,
+    time: Res<Time>,
+) {
+    for (bound, shrink) in &bound_query {
+        for (snake_entity, mut snake) in &mut snake_query {
+            if arena_contains(&bound.shape, snake.head_pos) {
+                continue;
+            }
+            if snake_starve(&mut snake, shrink.size_loss_per_second * time.delta_seconds()) {
+                commands.entity(snake_entity).insert(SnakeDead);
+            }
+        }
+    }
+}
+
+/// Dead snakes leave food along their body. When no snake is left the game is over,
+/// high scores wait for names, the level progress starts again and every player gets a new snake.
+#[allow(clippy::too_many_arguments)]
+fn snake_death_update(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    dead_query: Query<(Entity, &SnakeModel), With<SnakeDead>>,
+    alive_query: Query<&SnakeModel, Without<SnakeDead>>,
+    level: Res<Level>,
+    players: Res<LocalPlayers>,
+    mut score_query: Query<&mut Score>,
+    high_scores: Res<HighScores>,
+    mut name_entry: ResMut<NameEntry>,
+    mut progress_query: Query<&mut LevelProgress>,
+    the_game_query: Query<&TheGame>,
+) {
+    if dead_query.is_empty() {
+        return;
+    }
+    for (snake_entity, snake) in &dead_query {
+        food_spawn_on_death(&mut commands, &asset_server, &snake_node_positions(snake), snake_death_nutrition(snake));
+        for node in snake.body.iter() {
+            commands.entity(node.node_type).despawn();
+        }
+        commands.entity(snake_entity).despawn();
+    }
+    if alive_query.is_empty() {
+        let scores: Vec<(usize, i32)> = score_query.iter().map(|score| (score.player, score.score_num)).collect();
+        name_entry.scores = high_scores_game_over(&high_scores.entries, &scores);
+        for mut score in &mut score_query {
+            score.score_num = 0;
+        }
+        if let Ok(the_game) = the_game_query.get_single() {
+            for mut progress in &mut progress_query {
+                level_progress_reset(&mut commands, the_game, &mut progress);
+            }
+        }
+        for index in 0..players.count {
+            snake_spawn_player(&mut commands, &asset_server, &level, Player { index });
+        }
     }
 }
 
-fn keyboard_movement_up_down_impure(keyboard_input: &Res<ButtonInput<KeyCode>>) -> SnakeMoveDirection {
-    if keyboard_input.pressed(KeyCode::ArrowUp) { SnakeMoveDirection::Forward }
-    else if keyboard_input.pressed(KeyCode::ArrowDown) { SnakeMoveDirection::Backward }
+fn keyboard_movement_up_down_impure(keyboard_input: &Res<ButtonInput<KeyCode>>, controls: &PlayerControls) -> SnakeMoveDirection {
+    if keyboard_input.pressed(controls.forward) { SnakeMoveDirection::Forward }
+    else if keyboard_input.pressed(controls.backward) { SnakeMoveDirection::Backward }
     else { SnakeMoveDirection::Stop }
 }
 
-fn keyboard_rotation(keyboard_input: &Res<ButtonInput<KeyCode>>, snake: &SnakeModel, time: &Res<Time>) -> f32 {
+fn keyboard_boost(keyboard_input: &Res<ButtonInput<KeyCode>>, controls: &PlayerControls) -> bool {
+    keyboard_input.pressed(controls.boost)
+}
+
+fn keyboard_rotation(keyboard_input: &Res<ButtonInput<KeyCode>>, controls: &PlayerControls, snake: &SnakeModel, time: &Res<Time>) -> f32 {
     let unit: f32 = {
-        if keyboard_input.pressed(KeyCode::ArrowRight) { -1.0 }
-        else if keyboard_input.pressed(KeyCode::ArrowLeft) { 1.0 }
+        if keyboard_input.pressed(controls.right) { -1.0 }
+        else if keyboard_input.pressed(controls.left) { 1.0 }
         else { 0.0 }
     };
-    consts::PI / 180.0 * snake.rotation_speed_in_degrees * unit * time.delta_seconds()
+    let unit = if snake_controls_reversed(snake) { -unit } else { unit };
+    consts::PI / 180.0 * snake_rotation_speed_in_degrees(snake) * unit * time.delta_seconds()
 }
 
 fn draw_circle(gizmos: &mut Gizmos, position: Vec2, radius: f32, grid_query: &Query<&GridVisualDiagnostic>) {
@@ -54,22 +180,6 @@ fn draw_circle(gizmos: &mut Gizmos, position: Vec2, radius: f32, grid_query: &Qu
     }
 }
 
-fn draw_tail(gizmos: &mut Gizmos, radius: f32, snake: &SnakeModel, grid_query: &Query<&GridVisualDiagnostic>){
-    let mut distance = radius * 2.0;
-    for i in 1..=2 {
-        let shift_from_head: Vec2 = {
-            let x_tail = f32::cos(snake.head_direction_angle - consts::PI) * distance;
-            let y_tail = f32::sin(snake.head_direction_angle - consts::PI) * distance;
-            Vec2::new(x_tail, y_tail)
-        };
-
-        let tail_pos = snake.head_pos + shift_from_head;
-        let tail_radius = radius - (20.0 * i as f32);
-        distance += 75.0;
-        draw_circle(gizmos, tail_pos, tail_radius, &grid_query);
-    }
-}
-
 fn get_last_trace_index_before_clean(snake: &SnakeModel, gizmos: &mut Gizmos) -> i64 {
     let mut current_pos = snake.head_pos;
     let mut total_distance = 0.0;
@@ -79,7 +189,7 @@ fn get_last_trace_index_before_clean(snake: &SnakeModel, gizmos: &mut Gizmos) ->
         total_distance += current_pos.distance(i.pos);
         current_pos = i.pos;
         
-        if total_distance > 20.0 + snake.size * snake.node_radius * 2.0 {
+        if total_distance > 20.0 + snake_body_length(snake) {
             last_trace_index_before_clean = i.index;
             break;
         }
@@ -87,76 +197,111 @@ fn get_last_trace_index_before_clean(snake: &SnakeModel, gizmos: &mut Gizmos) ->
     return last_trace_index_before_clean;
 }
 
-fn draw_nodes(snake: &mut SnakeModel, gizmos: &mut Gizmos, query_visual_element: &mut Query<&mut Transform, With<CreatureBodyVisualElement>>) {
+fn draw_nodes(
+    snake: &mut SnakeModel, 
+    gizmos: &mut Gizmos, 
+    query_visual_element: &mut Query<(&mut Transform, &mut Handle<Image>), With<CreatureBodyVisualElement>>,
+    asset_server: &Res<AssetServer>,
+    grid_query: &Query<&GridVisualDiagnostic>,
+) {
     let mut current_pos = snake.head_pos;
     let step = snake.tracing_step;
     let mut color_change = 0;
     
-    for i in 0..=(snake.size) as i32 {
-        let distance_from_head = i as f32 * (snake.tracing_step * 2.0);
-        let trace_positions_iterator = snake.trace.iter().map(|p| p.pos);
-        let node_calc_result = calculate_node_pos_traced_on_distance_from_head(
-            snake.head_pos, 
-            snake.head_direction_angle,
-            trace_positions_iterator, 
-            distance_from_head
-        );
+    for i in 0..=snake_last_node_index(snake) as i32 {
+        let radius = snake.body[i as usize].radius;
+        let node_pos = snake.body[i as usize].pos;
         
-        //gizmos.circle_2d(node_pos, snake.node_radius, BLUE);
+        draw_circle(gizmos, node_pos, radius, grid_query);
         
         let color = Color::hsl(360.0 * color_change as f32 / step as f32, 0.95, 0.7);
         color_change += 1; 
 
-        gizmos.line_2d(current_pos, node_calc_result.position, color);
+        gizmos.line_2d(current_pos, node_pos, color);
         if i != 0 {
-            current_pos = node_calc_result.position;
+            current_pos = node_pos;
+        }
+
+        if i == 0 {
+            // head sprite and rotation are updated separately
+            continue;
         }
 
+        let keyframe = body_profile_keyframe(&snake.body_profile, snake_node_body_position(snake, i as usize));
+        // node spawned this frame gets its transform on the next frame
+        let Ok((mut node, mut texture)) = query_visual_element.get_mut(snake.body[i as usize].node_type) else { continue; };
         let snake_node = {
-            let mut node: Mut<Transform> = query_visual_element.get_mut(snake.body[i as usize].node_type).unwrap();
-            node.translation = Vec3::new(node_calc_result.position.x, node_calc_result.position.y, 0.0); 
-
-            //println!("{:?}", node_calc_result.directions.segment_distance_fraction.to_string());
-            let a = interpolate_direction(
-                node_calc_result.directions.direction_previous, 
-                node_calc_result.directions.direction_current,
-                node_calc_result.directions.direction_next,
-                node_calc_result.directions.segment_distance_fraction,
-            );
-            node.rotation = Quat::from_rotation_z(a + PI / 2.0 + PI);
+            node.translation = Vec3::new(node_pos.x, node_pos.y, 0.0); 
+            let scale = radius * 2.0 / keyframe.sprite_size;
+            node.scale = Vec3::new(scale, scale, 0.0);
+            if snake.body[i as usize].sprite != keyframe.sprite {
+                *texture = asset_server.load(keyframe.sprite);
+                snake.body[i as usize].sprite = keyframe.sprite;
+            }
+            node.rotation = Quat::from_rotation_z(snake.body[i as usize].direction + PI / 2.0 + PI);
         };
     }
 }
 
-fn snake_update (
+#[allow(clippy::too_many_arguments)]
+pub fn snake_update (
+    mut commands: Commands,
     mut gizmos: Gizmos, 
-    mut snake_query: Query<&mut SnakeModel>,
+    mut snake_query: Query<(&mut SnakeModel, &Player)>,
     keyboard_input: Res<ButtonInput<KeyCode>>,
     time: Res<Time>,
+    asset_server: Res<AssetServer>,
     grid_query: Query<&GridVisualDiagnostic>,
-    mut query_visual_element: Query<&mut Transform, With<CreatureBodyVisualElement>>,
+    mut query_visual_element: Query<(&mut Transform, &mut Handle<Image>), With<CreatureBodyVisualElement>>,
+    bound_query: Query<(&Bound, Option<&ArenaShrink>)>,
 ) {
-    for mut snake in &mut snake_query {
-        snake.head_direction_angle += keyboard_rotation(&keyboard_input, &snake, &time) * (snake.movement_speed / 4.0);
+    for (mut snake, player) in &mut snake_query {
+        let controls = player_controls(player.index);
+        snake.head_direction_angle += keyboard_rotation(&keyboard_input, &controls, &snake, &time) * (snake.movement_speed / 4.0);
 
-        let keyboard_up_down_input: SnakeMoveDirection = keyboard_movement_up_down_impure(&keyboard_input);
+        let mut keyboard_up_down_input: SnakeMoveDirection = keyboard_movement_up_down_impure(&keyboard_input, &controls);
+        if snake_controls_reversed(&snake) {
+            keyboard_up_down_input = snake_move_direction_reversed(keyboard_up_down_input);
+        }
+        snake.boosting = keyboard_boost(&keyboard_input, &controls)
+            && matches!(keyboard_up_down_input, SnakeMoveDirection::Forward)
+            && snake.size > snake.boost_min_size;
         head_move_pure(keyboard_up_down_input, time.delta_seconds(), &mut snake);
+        // the head slides along the arena outline instead of leaving it
+        for (bound, shrink) in &bound_query {
+            snake.head_pos = arena_push_inside(arena_wall(bound, shrink), snake.head_pos, snake.head_radius);
+        }
+        snake_growth_update(&mut snake, time.delta_seconds());
+        snake_effects_update(&mut snake, time.delta_seconds());
+
+        let pellets = snake_boost_update(&mut snake, time.delta_seconds());
+        if pellets > 0 {
+            let trace_positions: Vec<Vec2> = snake_boost_pellet_distances(&snake, pellets).into_iter()
+                .map(|distance| calculate_node_pos_traced_on_distance_from_head(
+                    snake.head_pos,
+                    snake.head_direction_angle,
+                    snake.trace.iter().map(|p| p.pos),
+                    distance,
+                ).position)
+                .collect();
+            for pos in food_spawn_positions_along_body(&trace_positions, 5.0, &mut rand::thread_rng()) {
+                food_pellet_spawn(&mut commands, &asset_server, pos, snake.boost_pellet_size);
+            }
+        }
+        spine_grow(&mut commands, &asset_server, &mut snake);
+        spine_shrink(&mut commands, &mut snake);
+        spine_distances_update(&mut snake);
+        spine_positions_update(&mut snake);
 
-        let node_pos = draw_nodes(&mut snake, &mut gizmos, &mut query_visual_element);
-        
         let last_trace_index_before_clean = get_last_trace_index_before_clean(&snake, &mut gizmos);
         clear_extra_traces(&mut snake.trace, last_trace_index_before_clean);
 
-        draw_circle(&mut gizmos, snake.head_pos, snake.head_radius, &grid_query); // draws hidden snake head in gizmos
-
-        draw_tail(&mut gizmos, snake.head_radius, &snake, &grid_query);
-
-        draw_nodes(&mut snake, &mut gizmos, &mut query_visual_element);
+        draw_nodes(&mut snake, &mut gizmos, &mut query_visual_element, &asset_server, &grid_query);
 
         let snake_head = {
-            let mut head: Mut<Transform> = query_visual_element.get_mut(snake.body[0].node_type).unwrap();
+            let (mut head, _) = query_visual_element.get_mut(snake.body[0].node_type).unwrap();
             head.translation = Vec3::new(snake.head_pos.x, snake.head_pos.y, 0.0); 
             head.rotation = Quat::from_rotation_z(snake.head_direction_angle + PI / 2.0 + PI);
         };  
     }
-} 
\ No newline at end of file
+}
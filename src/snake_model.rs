@@ -1,7 +1,11 @@
 use bevy::math::Vec2;
 use bevy::prelude::{Component, Entity};
 use std::collections::LinkedList;
-use std::f32::consts::PI;
+
+use crate::body_profile::*;
+use crate::foo::interpolate_direction;
+use crate::food_kind::{food_effect_duration, FoodEffect};
+use crate::trace_position_calculator::calculate_node_pos_traced_on_distance_from_head;
 
 #[derive(PartialEq)]
 #[derive(Debug)]
@@ -19,7 +23,44 @@ pub enum SnakeSpineNodeType {
 
 pub struct SnakeSpineNode{
     pub distance_from_head: f32,
-    pub node_type: Entity
+    pub node_type: Entity,
+    // current node radius taken from the body profile
+    pub radius: f32,
+    // sprite currently loaded for this node
+    pub sprite: &'static str,
+    // node position on the trace and its direction angle, updated every frame
+    pub pos: Vec2,
+    pub direction: f32,
+}
+
+/// Eaten food travelling from head to tail as a radius bump.
+pub struct GrowthBulge {
+    pub distance_from_head: f32,
+    // number of nodes added to the tail when the bulge reaches it
+    pub amount: f32,
+}
+
+// shrinking food can not make the snake shorter than this size
+const SNAKE_MIN_SIZE: f32 = 1.0;
+
+// nodes close to the head always touch it, so they can not kill the snake
+const SELF_COLLISION_SKIP_NODES: usize = 3;
+
+// nutrition of food left by a dead snake for every unit of its size
+const SNAKE_DEATH_NUTRITION_PER_SIZE: f32 = 0.5;
+
+// distance along the body between pellets dropped by boost in the same frame
+const BOOST_PELLET_SPACING: f32 = 10.0;
+
+// distance along the body from the bulge center where the radius bump disappears
+const GROWTH_BULGE_HALF_WIDTH: f32 = 30.0;
+// radius increase in the bulge center for every node of amount
+const GROWTH_BULGE_HEIGHT: f32 = 6.0;
+
+/// Food effect which is active on the snake for remaining_seconds.
+pub struct SnakeEffect {
+    pub effect: FoodEffect,
+    pub remaining_seconds: f32,
 }
 
 pub enum BodyType {
@@ -45,7 +86,27 @@ pub struct SnakeModel {
     pub tracing_step: f32,
     // NOT number of foods eaten by snake // number of nodes drawn
     pub size: f32, 
-    pub node_radius: f32,
+    // node radius and sprite along the body from head to tail
+    pub body_profile: BodyProfile,
+    // size which is reached when all swallowed food gets to the tail and all new nodes are extended
+    pub size_target: f32,
+    pub growth_bulges: Vec<GrowthBulge>,
+    // bulge movement speed along the body in units per second
+    pub growth_bulge_speed: f32,
+    // seconds to extend one new node at the tail
+    pub growth_duration: f32,
+    // true while boost key is pressed and there is body length to burn
+    pub boosting: bool,
+    pub boost_speed_multiplier: f32,
+    // size lost every second of boost
+    pub boost_size_drain_per_second: f32,
+    // boost is not possible when size gets to this value
+    pub boost_min_size: f32,
+    // size lost for every food pellet dropped behind the snake
+    pub boost_pellet_size: f32,
+    // size lost since the last dropped pellet
+    pub boost_size_lost: f32,
+    pub effects: Vec<SnakeEffect>,
 
     pub body: Vec<SnakeSpineNode>,
 }
@@ -56,36 +117,281 @@ pub enum  SnakeMoveDirection {
     Stop
 }
 
-pub fn snake_model_new(i: i32) -> SnakeModel {
-    let head_pos = Vec2::new(0.0, i as f32 * -100.0);
+/// New snake with the head in head_pos looking to head_direction_angle.
+pub fn snake_model_at(head_pos: Vec2, head_direction_angle: f32) -> SnakeModel {
     let trace_item = TraceItem {
         pos: head_pos,
         index: 0,
     };
+    let body_profile = body_profile_default();
     SnakeModel {
         head_pos,
-        head_direction_angle: PI / 2.0,
-        head_radius: 50.0,
+        head_direction_angle,
+        head_radius: body_profile_radius(&body_profile, 0.0),
         movement_speed: 150.0,
         rotation_speed_in_degrees: 3.0,
         trace_counter: 0,
         trace: LinkedList::from([trace_item]),
         tracing_step: 10.0,
         size: 5.0,
-        node_radius: 10.0,
+        body_profile,
+        size_target: 5.0,
+        growth_bulges: vec![],
+        growth_bulge_speed: 300.0,
+        growth_duration: 0.5,
+        boosting: false,
+        boost_speed_multiplier: 2.0,
+        boost_size_drain_per_second: 1.0,
+        boost_min_size: 3.0,
+        boost_pellet_size: 0.25,
+        boost_size_lost: 0.0,
+        effects: vec![],
         body: vec![],
     }
     
 }
 
-pub fn snake_head_new_list() -> Vec<SnakeModel> {
-    let mut result: Vec<SnakeModel> = Vec::new();
-    for i in 0..1 {
-        result.push(snake_model_new(i));
+/// Normalized body position of the node: 0.0 for the head and 1.0 for the last node.
+pub fn snake_node_body_position(snake: &SnakeModel, node_index: usize) -> f32 {
+    if snake.size <= 0.0 { 0.0 }
+    else { node_index as f32 / snake.size.floor() }
+}
+
+pub fn snake_node_radius(snake: &SnakeModel, node_index: usize) -> f32 {
+    body_profile_radius(&snake.body_profile, snake_node_body_position(snake, node_index))
+}
+
+/// Number of the last node drawn. While the tail is extending it is the partially extended node.
+pub fn snake_last_node_index(snake: &SnakeModel) -> usize {
+    snake.size.ceil() as usize
+}
+
+/// Radius increase at the given distance from head made by swallowed food.
+pub fn snake_bulge_radius(snake: &SnakeModel, distance_from_head: f32) -> f32 {
+    let mut result = 0.0;
+    for bulge in snake.growth_bulges.iter() {
+        let fraction = 1.0 - (distance_from_head - bulge.distance_from_head).abs() / GROWTH_BULGE_HALF_WIDTH;
+        if fraction > 0.0 {
+            result += fraction * GROWTH_BULGE_HEIGHT * bulge.amount;
+        }
     }
     result
 }
 
+/// Updates node radii from the body profile and places every node right behind the previous one,
+/// so the distance between two neighbour nodes is the sum of their radii.
+/// Node after the whole part of size is extending: it is placed closer according to the size fraction.
+pub fn spine_distances_update(snake: &mut SnakeModel) {
+    let mut distance_from_head = 0.0;
+    let mut previous_radius = 0.0;
+    for i in 0..snake.body.len() {
+        let radius = snake_node_radius(snake, i);
+        if i != 0 {
+            let extension = (snake.size - (i - 1) as f32).clamp(0.0, 1.0);
+            distance_from_head += (previous_radius + radius) * extension;
+        }
+        snake.body[i].radius = radius + snake_bulge_radius(snake, distance_from_head);
+        snake.body[i].distance_from_head = distance_from_head;
+        previous_radius = radius;
+    }
+}
+
+/// Places drawn nodes on the trace according to their distance_from_head.
+pub fn spine_positions_update(snake: &mut SnakeModel) {
+    for i in 0..snake.body.len().min(snake_last_node_index(snake) + 1) {
+        // trace calculation gives NaN for zero distance
+        if snake.body[i].distance_from_head <= 0.0 {
+            snake.body[i].pos = snake.head_pos;
+            snake.body[i].direction = snake.head_direction_angle;
+            continue;
+        }
+        let node_calc_result = calculate_node_pos_traced_on_distance_from_head(
+            snake.head_pos,
+            snake.head_direction_angle,
+            snake.trace.iter().map(|p| p.pos),
+            snake.body[i].distance_from_head,
+        );
+        snake.body[i].pos = node_calc_result.position;
+        snake.body[i].direction = interpolate_direction(
+            node_calc_result.directions.direction_previous,
+            node_calc_result.directions.direction_current,
+            node_calc_result.directions.direction_next,
+            node_calc_result.directions.segment_distance_fraction,
+        );
+    }
+}
+
+/// Distance along the trace from the head to the last drawn node.
+pub fn snake_body_length(snake: &SnakeModel) -> f32 {
+    match snake.body.iter().take(snake_last_node_index(snake) + 1).next_back() {
+        None => 0.0,
+        Some(node) => node.distance_from_head,
+    }
+}
+
+/// Starts moving swallowed food from head to tail.
+pub fn snake_swallow(snake: &mut SnakeModel, amount: f32) {
+    snake.growth_bulges.push(GrowthBulge {
+        distance_from_head: 0.0,
+        amount,
+    });
+}
+
+/// Moves bulges toward the tail. Bulge which reached the tail increases target size,
+/// and size follows target size extending one node per growth_duration seconds.
+pub fn snake_growth_update(snake: &mut SnakeModel, time_delta_seconds: f32) {
+    let body_length = snake_body_length(snake);
+    let bulge_move = snake.growth_bulge_speed * time_delta_seconds;
+    let mut arrived = 0.0;
+    snake.growth_bulges.retain_mut(|bulge| {
+        bulge.distance_from_head += bulge_move;
+        if bulge.distance_from_head >= body_length {
+            arrived += bulge.amount;
+            false
+        }
+        else { true }
+    });
+    snake.size_target += arrived;
+
+    let size_step = time_delta_seconds / snake.growth_duration;
+    if snake.size < snake.size_target {
+        snake.size = f32::min(snake.size + size_step, snake.size_target);
+    }
+}
+
+/// Linear speed including boost and speed effects.
+pub fn snake_movement_speed(snake: &SnakeModel) -> f32 {
+    let mut speed = snake.movement_speed;
+    if snake.boosting {
+        speed *= snake.boost_speed_multiplier;
+    }
+    for snake_effect in snake.effects.iter() {
+        if let FoodEffect::SpeedBoost { multiplier, .. } = snake_effect.effect {
+            speed *= multiplier;
+        }
+    }
+    speed
+}
+
+/// Rotation speed including turn effects.
+pub fn snake_rotation_speed_in_degrees(snake: &SnakeModel) -> f32 {
+    let mut rotation_speed = snake.rotation_speed_in_degrees;
+    for snake_effect in snake.effects.iter() {
+        if let FoodEffect::TightTurn { multiplier, .. } = snake_effect.effect {
+            rotation_speed *= multiplier;
+        }
+    }
+    rotation_speed
+}
+
+pub fn snake_is_ghost(snake: &SnakeModel) -> bool {
+    snake.effects.iter().any(|snake_effect| matches!(snake_effect.effect, FoodEffect::Ghost { .. }))
+}
+
+/// True if the head touching the own body node kills the snake. A ghost passes through its own body.
+pub fn snake_self_collision_kills(snake: &SnakeModel, node_index: usize) -> bool {
+    node_index >= SELF_COLLISION_SKIP_NODES && !snake_is_ghost(snake)
+}
+
+/// Food movement made by magnet effects of the snake during the time delta.
+pub fn snake_magnet_pull(snake: &SnakeModel, food_pos: Vec2, time_delta_seconds: f32) -> Vec2 {
+    let mut result = Vec2::ZERO;
+    for snake_effect in snake.effects.iter() {
+        if let FoodEffect::Magnet { radius, speed, .. } = snake_effect.effect {
+            let to_head = snake.head_pos - food_pos;
+            let distance = to_head.length();
+            if distance < radius && distance > 0.0 {
+                result += to_head / distance * f32::min(speed * time_delta_seconds, distance);
+            }
+        }
+    }
+    result
+}
+
+pub fn snake_controls_reversed(snake: &SnakeModel) -> bool {
+    snake.effects.iter().any(|snake_effect| matches!(snake_effect.effect, FoodEffect::ReverseControls { .. }))
+}
+
+pub fn snake_move_direction_reversed(direction: SnakeMoveDirection) -> SnakeMoveDirection {
+    match direction {
+        SnakeMoveDirection::Forward => SnakeMoveDirection::Backward,
+        SnakeMoveDirection::Backward => SnakeMoveDirection::Forward,
+        SnakeMoveDirection::Stop => SnakeMoveDirection::Stop,
+    }
+}
+
+/// Removes nodes from the tail right away, not below SNAKE_MIN_SIZE.
+pub fn snake_shrink(snake: &mut SnakeModel, amount: f32) {
+    snake.size = f32::max(snake.size - amount, SNAKE_MIN_SIZE);
+    snake.size_target = f32::max(snake.size_target - amount, SNAKE_MIN_SIZE);
+}
+
+/// Timed effects are stacked: the same effect eaten twice works twice as strong.
+pub fn snake_effect_add(snake: &mut SnakeModel, effect: &FoodEffect) {
+    if let FoodEffect::Shrink { amount } = effect {
+        snake_shrink(snake, *amount);
+        return;
+    }
+    snake.effects.push(SnakeEffect {
+        effect: effect.clone(),
+        remaining_seconds: food_effect_duration(effect),
+    });
+}
+
+/// Takes size from a snake outside the safe zone. Returns true when nothing is left to take.
+pub fn snake_starve(snake: &mut SnakeModel, amount: f32) -> bool {
+    snake_shrink(snake, amount);
+    snake.size <= SNAKE_MIN_SIZE
+}
+
+/// Positions of all drawn spine nodes from the head to the tail.
+pub fn snake_node_positions(snake: &SnakeModel) -> Vec<Vec2> {
+    snake.body.iter().take(snake_last_node_index(snake) + 1).map(|node| node.pos).collect()
+}
+
+/// Total nutrition of food which a dead snake turns into.
+pub fn snake_death_nutrition(snake: &SnakeModel) -> f32 {
+    snake.size * SNAKE_DEATH_NUTRITION_PER_SIZE
+}
+
+/// Counts down effect durations and removes finished effects.
+pub fn snake_effects_update(snake: &mut SnakeModel, time_delta_seconds: f32) {
+    snake.effects.retain_mut(|snake_effect| {
+        snake_effect.remaining_seconds -= time_delta_seconds;
+        snake_effect.remaining_seconds > 0.0
+    });
+}
+
+/// Burns body length while boosting, but not below boost_min_size.
+/// Returns number of food pellets which must be dropped behind the snake.
+pub fn snake_boost_update(snake: &mut SnakeModel, time_delta_seconds: f32) -> i32 {
+    if !snake.boosting {
+        return 0;
+    }
+    let drain = f32::min(snake.boost_size_drain_per_second * time_delta_seconds, snake.size - snake.boost_min_size);
+    if drain <= 0.0 {
+        snake.boosting = false;
+        return 0;
+    }
+    snake.size -= drain;
+    snake.size_target -= drain;
+    snake.boost_size_lost += drain;
+
+    let mut pellets = 0;
+    while snake.boost_size_lost >= snake.boost_pellet_size {
+        snake.boost_size_lost -= snake.boost_pellet_size;
+        pellets += 1;
+    }
+    pellets
+}
+
+/// Distances from the head where boost pellets are dropped: the first at the tail and every next one
+/// BOOST_PELLET_SPACING closer to the head, so they are not on top of each other.
+pub fn snake_boost_pellet_distances(snake: &SnakeModel, pellets: i32) -> Vec<f32> {
+    let body_length = snake_body_length(snake);
+    (0..pellets).map(|i| f32::max(body_length - i as f32 * BOOST_PELLET_SPACING, 0.0)).collect()
+}
+
 pub fn clear_extra_traces(list: &mut LinkedList<TraceItem>, max_index: i64) {
     loop {
         match list.back() {
@@ -100,13 +406,44 @@ pub fn clear_extra_traces(list: &mut LinkedList<TraceItem>, max_index: i64) {
     }
 }
 
+/// Moving backward the head follows its own trace back, so nodes placed along the trace keep their spacing.
+/// The tail leads and continues straight after the end of the trace.
+fn head_move_backward_pure(time_delta_seconds: f32, snake: &mut SnakeModel) {
+    let distance = snake_movement_speed(snake) * time_delta_seconds;
+    let new_head = calculate_node_pos_traced_on_distance_from_head(
+        snake.head_pos,
+        snake.head_direction_angle,
+        snake.trace.iter().map(|p| p.pos),
+        distance,
+    );
+
+    // remove trace points passed by the head, the last one is always kept
+    let mut passed_distance = 0.0;
+    let mut current_pos = snake.head_pos;
+    while snake.trace.len() > 1 {
+        let front_pos = snake.trace.front().unwrap().pos;
+        passed_distance += current_pos.distance(front_pos);
+        if passed_distance >= distance {
+            break;
+        }
+        current_pos = front_pos;
+        snake.trace.pop_front();
+    }
+
+    snake.head_pos = new_head.position;
+    snake.head_direction_angle = new_head.directions.direction_current;
+}
+
 pub fn head_move_pure(keyboard_up_down_input: SnakeMoveDirection, time_delta_seconds: f32, snake: &mut SnakeModel) {
     let keyboard_up_down_input_ratio: f32 = match keyboard_up_down_input {
         SnakeMoveDirection::Forward => { 1.0 }
-        SnakeMoveDirection::Backward => { -1.0 }
+        SnakeMoveDirection::Backward => {
+            head_move_backward_pure(time_delta_seconds, snake);
+            return;
+        }
         SnakeMoveDirection::Stop => { 0.0 }
     };
-    let movement = keyboard_up_down_input_ratio * snake.movement_speed;
+    let movement = keyboard_up_down_input_ratio * snake_movement_speed(snake);
     let x_head = f32::cos(snake.head_direction_angle) * movement * time_delta_seconds;
     let y_head = f32::sin(snake.head_direction_angle) * movement * time_delta_seconds;
 
@@ -1,6 +1,8 @@
-use bevy::prelude::Vec2;
+use bevy::prelude::{Entity, Vec2};
 use std::collections::LinkedList;
+use crate::food_kind::FoodEffect;
 use crate::snake_model::*;
+use crate::trace_position_calculator::calculate_node_pos_traced_on_distance_from_head;
 
 #[cfg(test)]
 mod tests {
@@ -20,7 +22,7 @@ mod tests {
 
     #[test]
     fn no_move_because_not_key_input() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let traces_original = snake.trace.clone();
         snake.tracing_step = 50.0;
         head_move_pure(SnakeMoveDirection::Stop, 10.0, &mut snake);
@@ -33,7 +35,7 @@ mod tests {
 
     #[test]
     fn move_forward_north() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let traces_original = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.head_direction_angle = PI / 2.0;
@@ -47,7 +49,7 @@ mod tests {
 
     #[test]
     fn move_backward_south() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let traces_original = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.movement_speed = 3.0;
@@ -60,7 +62,7 @@ mod tests {
 
     #[test]
     fn move_forward_south() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let traces_original = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.head_direction_angle = -PI / 2.0;
@@ -74,7 +76,7 @@ mod tests {
 
     #[test]
     fn move_backward_north() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let traces_original = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.head_direction_angle = -PI / 2.0;
@@ -88,7 +90,7 @@ mod tests {
 
     #[test]
     fn trace_track_move_up() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let mut traces_expected = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.head_direction_angle = PI / 2.0;
@@ -114,7 +116,7 @@ mod tests {
 
     #[test]
     fn trace_track_move_up_with_diff_headpos() {
-        let mut snake = snake_model_new(0);
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
         let mut traces_expected = snake.trace.clone();
         snake.tracing_step = 50.0;
         snake.head_pos = Vec2::new(0.0, 100.0);
@@ -200,4 +202,328 @@ mod tests {
         ];
         assert_eq!(actual, expected)
     }
-}
\ No newline at end of file
+
+    fn snake_with_body(node_count: usize) -> SnakeModel {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        for _ in 0..node_count {
+            snake.body.push(SnakeSpineNode {
+                distance_from_head: 50.0,
+                node_type: Entity::PLACEHOLDER,
+                radius: 0.0,
+                sprite: "SpinePart.png",
+                pos: Vec2::ZERO,
+                direction: 0.0,
+            });
+        }
+        snake
+    }
+
+    #[test]
+    fn spine_distances_from_adjacent_radii() {
+        let mut snake = snake_with_body(6);
+        spine_distances_update(&mut snake);
+
+        assert_float_eq(snake.body[0].distance_from_head, 0.0);
+        assert_float_eq(snake.body[0].radius, snake.head_radius);
+        for i in 1..snake.body.len() {
+            let previous = &snake.body[i - 1];
+            let current = &snake.body[i];
+            assert_float_eq(current.radius, snake_node_radius(&snake, i));
+            assert_float_eq(current.distance_from_head - previous.distance_from_head, previous.radius + current.radius);
+        }
+    }
+
+    #[test]
+    fn spine_distances_do_not_depend_on_tracing_step() {
+        let mut snake_a = snake_with_body(6);
+        let mut snake_b = snake_with_body(6);
+        snake_a.tracing_step = 1.0;
+        snake_b.tracing_step = 50.0;
+        spine_distances_update(&mut snake_a);
+        spine_distances_update(&mut snake_b);
+
+        for (a, b) in snake_a.body.iter().zip(snake_b.body.iter()) {
+            assert_float_eq(a.distance_from_head, b.distance_from_head);
+        }
+    }
+
+    #[test]
+    fn body_length_is_last_drawn_node_distance() {
+        let mut snake = snake_with_body(10);
+        snake.size = 5.0;
+        spine_distances_update(&mut snake);
+        assert_float_eq(snake_body_length(&snake), snake.body[5].distance_from_head);
+    }
+
+    #[test]
+    fn swallowed_food_moves_to_tail_as_bulge() {
+        let mut snake = snake_with_body(6);
+        spine_distances_update(&mut snake);
+        snake_swallow(&mut snake, 1.0);
+        assert_eq!(snake.growth_bulges.len(), 1);
+
+        snake_growth_update(&mut snake, 0.1);
+        assert_float_eq(snake.growth_bulges[0].distance_from_head, snake.growth_bulge_speed * 0.1);
+        // size does not change until the bulge gets to the tail
+        assert_float_eq(snake.size, 5.0);
+        assert_float_eq(snake.size_target, 5.0);
+
+        spine_distances_update(&mut snake);
+        let bulge_distance = snake.growth_bulges[0].distance_from_head;
+        assert!(snake_bulge_radius(&snake, bulge_distance) > 0.0);
+        assert_float_eq(snake_bulge_radius(&snake, bulge_distance + 1000.0), 0.0);
+    }
+
+    #[test]
+    fn tail_extends_smoothly_after_bulge_arrives() {
+        let mut snake = snake_with_body(8);
+        spine_distances_update(&mut snake);
+        snake_swallow(&mut snake, 1.0);
+        snake.growth_bulges[0].distance_from_head = snake_body_length(&snake);
+
+        let growth_duration = snake.growth_duration;
+        snake_growth_update(&mut snake, growth_duration / 2.0);
+        assert!(snake.growth_bulges.is_empty());
+        assert_float_eq(snake.size_target, 6.0);
+        assert_float_eq(snake.size, 5.0 + 0.5);
+
+        spine_distances_update(&mut snake);
+        let full_step = snake_node_radius(&snake, 5) + snake_node_radius(&snake, 6);
+        assert_float_eq(snake.body[6].distance_from_head - snake.body[5].distance_from_head, full_step * 0.5);
+
+        snake_growth_update(&mut snake, growth_duration);
+        assert_float_eq(snake.size, 6.0);
+    }
+
+    #[test]
+    fn boost_multiplies_speed() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake.tracing_step = 50.0;
+        snake.movement_speed = 3.0;
+        snake.boosting = true;
+        head_move_pure(SnakeMoveDirection::Forward, 1.0, &mut snake);
+        assert_vec2_eq(snake.head_pos, Vec2::new(0.0, 3.0 * snake.boost_speed_multiplier));
+    }
+
+    #[test]
+    fn boost_drains_size_and_drops_pellets() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake.boosting = true;
+        snake.boost_size_drain_per_second = 1.0;
+        snake.boost_pellet_size = 0.25;
+        let pellets = snake_boost_update(&mut snake, 0.5);
+        assert_eq!(pellets, 2);
+        assert_float_eq(snake.size, 4.5);
+        assert_float_eq(snake.size_target, 4.5);
+        assert!(snake.boosting);
+    }
+
+    #[test]
+    fn boost_pellets_are_spread_from_tail_to_head() {
+        let mut snake = snake_with_body(6);
+        spine_distances_update(&mut snake);
+        let body_length = snake_body_length(&snake);
+        let distances = snake_boost_pellet_distances(&snake, 3);
+        assert_eq!(distances.len(), 3);
+        assert_float_eq(distances[0], body_length);
+        assert!(distances[1] < distances[0] && distances[2] < distances[1]);
+        assert!(snake_boost_pellet_distances(&snake, 100).iter().all(|distance| *distance >= 0.0));
+    }
+
+    #[test]
+    fn boost_stops_on_min_size() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake.boosting = true;
+        snake.boost_min_size = 4.0;
+        snake.boost_size_drain_per_second = 10.0;
+        snake_boost_update(&mut snake, 1.0);
+        assert_float_eq(snake.size, 4.0);
+
+        let pellets = snake_boost_update(&mut snake, 1.0);
+        assert_eq!(pellets, 0);
+        assert_float_eq(snake.size, 4.0);
+        assert!(!snake.boosting);
+    }
+
+    #[test]
+    fn no_drain_without_boost() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        let pellets = snake_boost_update(&mut snake, 1.0);
+        assert_eq!(pellets, 0);
+        assert_float_eq(snake.size, 5.0);
+    }
+
+    fn node_positions(snake: &SnakeModel, distances: &[f32]) -> Vec<Vec2> {
+        distances.iter().map(|distance| {
+            calculate_node_pos_traced_on_distance_from_head(
+                snake.head_pos,
+                snake.head_direction_angle,
+                snake.trace.iter().map(|p| p.pos),
+                *distance,
+            ).position
+        }).collect()
+    }
+
+    #[test]
+    fn move_backward_keeps_node_spacing() {
+        let mut snake = snake_with_body(6);
+        spine_distances_update(&mut snake);
+        let distances: Vec<f32> = snake.body.iter().map(|node| node.distance_from_head).collect();
+        for _ in 0..60 {
+            head_move_pure(SnakeMoveDirection::Forward, 1.0 / 60.0, &mut snake);
+        }
+        for _ in 0..30 {
+            head_move_pure(SnakeMoveDirection::Backward, 1.0 / 60.0, &mut snake);
+
+            let positions = node_positions(&snake, &distances);
+            for i in 1..positions.len() {
+                let spacing = distances[i] - distances[i - 1];
+                assert_float_eq(positions[i].distance(positions[i - 1]), spacing);
+            }
+        }
+        // half of the forward movement is reversed
+        assert_vec2_eq(snake.head_pos, Vec2::new(0.0, snake.movement_speed / 2.0));
+        assert_float_eq(snake.head_direction_angle, PI / 2.0);
+    }
+
+    #[test]
+    fn move_backward_follows_trace() {
+        let mut snake = snake_with_body(6);
+        spine_distances_update(&mut snake);
+        let distances: Vec<f32> = snake.body.iter().map(|node| node.distance_from_head).collect();
+        for _ in 0..30 {
+            head_move_pure(SnakeMoveDirection::Forward, 1.0 / 60.0, &mut snake);
+        }
+        for _ in 0..60 {
+            snake.head_direction_angle += 0.02;
+            head_move_pure(SnakeMoveDirection::Forward, 1.0 / 60.0, &mut snake);
+        }
+
+        let backward_distance = snake.movement_speed / 60.0 * 10.0;
+        let shifted_distances: Vec<f32> = distances.iter().map(|distance| distance + backward_distance).collect();
+        let expected = node_positions(&snake, &shifted_distances);
+
+        for _ in 0..10 {
+            head_move_pure(SnakeMoveDirection::Backward, 1.0 / 60.0, &mut snake);
+        }
+        let actual = node_positions(&snake, &distances);
+        for (expected_pos, actual_pos) in expected.iter().zip(actual.iter()) {
+            assert!(expected_pos.distance(*actual_pos) < 0.01);
+        }
+    }
+
+    #[test]
+    fn speed_effect_lasts_for_duration() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake_effect_add(&mut snake, &FoodEffect::SpeedBoost { multiplier: 2.0, duration: 1.0 });
+        assert_float_eq(snake_movement_speed(&snake), snake.movement_speed * 2.0);
+
+        snake_effects_update(&mut snake, 0.5);
+        assert_float_eq(snake_movement_speed(&snake), snake.movement_speed * 2.0);
+
+        snake_effects_update(&mut snake, 0.6);
+        assert!(snake.effects.is_empty());
+        assert_float_eq(snake_movement_speed(&snake), snake.movement_speed);
+    }
+
+    #[test]
+    fn turn_effect_multiplies_rotation_speed() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake_effect_add(&mut snake, &FoodEffect::TightTurn { multiplier: 2.0, duration: 1.0 });
+        snake_effect_add(&mut snake, &FoodEffect::TightTurn { multiplier: 2.0, duration: 2.0 });
+        assert_float_eq(snake_rotation_speed_in_degrees(&snake), snake.rotation_speed_in_degrees * 4.0);
+
+        snake_effects_update(&mut snake, 1.5);
+        assert_float_eq(snake_rotation_speed_in_degrees(&snake), snake.rotation_speed_in_degrees * 2.0);
+    }
+
+    #[test]
+    fn magnet_pulls_food_in_radius() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        assert_vec2_eq(snake_magnet_pull(&snake, Vec2::new(50.0, 0.0), 1.0), Vec2::ZERO);
+
+        snake_effect_add(&mut snake, &FoodEffect::Magnet { radius: 100.0, speed: 10.0, duration: 1.0 });
+        assert_vec2_eq(snake_magnet_pull(&snake, Vec2::new(50.0, 0.0), 1.0), Vec2::new(-10.0, 0.0));
+        // food does not jump over the head
+        assert_vec2_eq(snake_magnet_pull(&snake, Vec2::new(5.0, 0.0), 1.0), Vec2::new(-5.0, 0.0));
+        assert_vec2_eq(snake_magnet_pull(&snake, Vec2::new(150.0, 0.0), 1.0), Vec2::ZERO);
+    }
+
+    #[test]
+    fn head_is_killed_by_own_body_behind_the_neck() {
+        let snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        assert!(!snake_self_collision_kills(&snake, 1));
+        assert!(!snake_self_collision_kills(&snake, 2));
+        assert!(snake_self_collision_kills(&snake, 3));
+    }
+
+    #[test]
+    fn ghost_ignores_self_collision() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake_effect_add(&mut snake, &FoodEffect::Ghost { duration: 1.0 });
+        assert!(snake_is_ghost(&snake));
+        assert!(!snake_self_collision_kills(&snake, 5));
+
+        snake_effects_update(&mut snake, 1.1);
+        assert!(!snake_is_ghost(&snake));
+        assert!(snake_self_collision_kills(&snake, 5));
+    }
+
+    #[test]
+    fn shrink_effect_is_instant() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake_effect_add(&mut snake, &FoodEffect::Shrink { amount: 2.0 });
+        assert!(snake.effects.is_empty());
+        assert_float_eq(snake.size, 3.0);
+        assert_float_eq(snake.size_target, 3.0);
+        assert_eq!(snake_last_node_index(&snake), 3);
+    }
+
+    #[test]
+    fn shrink_keeps_min_size() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        snake_shrink(&mut snake, 100.0);
+        assert_float_eq(snake.size, 1.0);
+        assert_float_eq(snake.size_target, 1.0);
+    }
+
+    #[test]
+    fn reverse_controls_effect() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        assert!(!snake_controls_reversed(&snake));
+        snake_effect_add(&mut snake, &FoodEffect::ReverseControls { duration: 1.0 });
+        assert!(snake_controls_reversed(&snake));
+        assert!(matches!(snake_move_direction_reversed(SnakeMoveDirection::Forward), SnakeMoveDirection::Backward));
+        assert!(matches!(snake_move_direction_reversed(SnakeMoveDirection::Stop), SnakeMoveDirection::Stop));
+        snake_effects_update(&mut snake, 1.0);
+        assert!(!snake_controls_reversed(&snake));
+    }
+
+    #[test]
+    fn dead_snake_food_is_proportional_to_size() {
+        let mut snake = snake_with_body(6);
+        let small = snake_death_nutrition(&snake);
+        snake.size *= 2.0;
+        assert_float_eq(snake_death_nutrition(&snake), small * 2.0);
+    }
+
+    #[test]
+    fn dead_snake_food_is_at_every_drawn_node() {
+        let mut snake = snake_with_body(10);
+        snake.size = 4.5;
+        spine_distances_update(&mut snake);
+        spine_positions_update(&mut snake);
+        let positions = snake_node_positions(&snake);
+        assert_eq!(positions.len(), snake_last_node_index(&snake) + 1);
+        assert_vec2_eq(positions[0], snake.head_pos);
+        assert_vec2_eq(positions[3], snake.body[3].pos);
+    }
+
+    #[test]
+    fn starving_snake_dies_on_min_size() {
+        let mut snake = snake_model_at(Vec2::ZERO, PI / 2.0);
+        assert!(!snake_starve(&mut snake, 1.0));
+        assert_float_eq(snake.size, 4.0);
+        assert!(snake_starve(&mut snake, 10.0));
+    }
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+
+use crate::food::Food;
+use crate::obstacle::{obstacle_bounding_circle, Obstacle};
+use crate::snake_model::{snake_last_node_index, SnakeModel};
+
+// cell size is close to the biggest query radius, so a query checks only a few cells
+const SPATIAL_HASH_CELL_SIZE: f32 = 100.0;
+
+pub struct SpatialHashPlugin;
+
+impl Plugin for SpatialHashPlugin {
+    fn build (&self, app: &mut App) {
+        app.insert_resource(spatial_hash_new(SPATIAL_HASH_CELL_SIZE));
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SpatialItem {
+    Food(Entity),
+    SnakeNode { snake: Entity, node_index: usize },
+    Obstacle(Entity),
+}
+
+pub struct SpatialEntry {
+    pub item: SpatialItem,
+    pub pos: Vec2,
+    pub radius: f32,
+}
+
+/// Uniform grid of circles rebuilt every frame, used to find food and snake nodes near a position
+/// without checking all of them.
+#[derive(Resource)]
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    entries: Vec<SpatialEntry>,
+}
+
+pub fn spatial_hash_new(cell_size: f32) -> SpatialHash {
+    SpatialHash {
+        cell_size,
+        cells: HashMap::new(),
+        entries: Vec::new(),
+    }
+}
+
+fn spatial_hash_cell(hash: &SpatialHash, pos: Vec2) -> (i32, i32) {
+    ((pos.x / hash.cell_size).floor() as i32, (pos.y / hash.cell_size).floor() as i32)
+}
+
+/// Cells are cleared but keep their memory for the next rebuild.
+/// Cells which stayed empty since the last rebuild are dropped, so an open world does not grow the hash forever.
+pub fn spatial_hash_clear(hash: &mut SpatialHash) {
+    hash.cells.retain(|_, cell| !cell.is_empty());
+    for cell in hash.cells.values_mut() {
+        cell.clear();
+    }
+    hash.entries.clear();
+}
+
+/// Circle is added to every cell which its bounding box touches.
+pub fn spatial_hash_insert(hash: &mut SpatialHash, item: SpatialItem, pos: Vec2, radius: f32) {
+    let entry_index = hash.entries.len();
+    hash.entries.push(SpatialEntry { item, pos, radius });
+    let (min_x, min_y) = spatial_hash_cell(hash, pos - Vec2::splat(radius));
+    let (max_x, max_y) = spatial_hash_cell(hash, pos + Vec2::splat(radius));
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            hash.cells.entry((x, y)).or_default().push(entry_index);
+        }
+    }
+}
+
+/// All circles which overlap the circle with the given position and radius.
+pub fn spatial_hash_query(hash: &SpatialHash, pos: Vec2, radius: f32) -> Vec<&SpatialEntry> {
+    let (min_x, min_y) = spatial_hash_cell(hash, pos - Vec2::splat(radius));
+    let (max_x, max_y) = spatial_hash_cell(hash, pos + Vec2::splat(radius));
+    let mut entry_indexes: Vec<usize> = Vec::new();
+    for x in min_x..=max_x {
+        for y in min_y..=max_y {
+            if let Some(cell) = hash.cells.get(&(x, y)) {
+                entry_indexes.extend(cell.iter());
+            }
+        }
+    }
+    // big circles are in several cells
+    entry_indexes.sort_unstable();
+    entry_indexes.dedup();
+    entry_indexes.into_iter()
+        .map(|entry_index| &hash.entries[entry_index])
+        .filter(|entry| entry.pos.distance(pos) < entry.radius + radius)
+        .collect()
+}
+
+/// Rebuilds the hash from food positions, spine node positions computed by the snake update and obstacles.
+pub fn spatial_hash_rebuild(
+    mut hash: ResMut<SpatialHash>,
+    food_query: Query<(Entity, &Food)>,
+    snake_query: Query<(Entity, &SnakeModel)>,
+    obstacle_query: Query<(Entity, &Obstacle)>,
+) {
+    spatial_hash_clear(&mut hash);
+    for (entity, food) in &food_query {
+        spatial_hash_insert(&mut hash, SpatialItem::Food(entity), food.pos, food.radius);
+    }
+    for (entity, snake) in &snake_query {
+        for (node_index, node) in snake.body.iter().enumerate().take(snake_last_node_index(snake) + 1) {
+            spatial_hash_insert(&mut hash, SpatialItem::SnakeNode { snake: entity, node_index }, node.pos, node.radius);
+        }
+    }
+    for (entity, obstacle) in &obstacle_query {
+        let (center, radius) = obstacle_bounding_circle(&obstacle.shape);
+        spatial_hash_insert(&mut hash, SpatialItem::Obstacle(entity), center, radius);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+    use std::time::Instant;
+
+    fn food_items(hash: &[&SpatialEntry]) -> Vec<Entity> {
+        let mut result: Vec<Entity> = hash.iter().filter_map(|entry| match entry.item {
+            SpatialItem::Food(entity) => Some(entity),
+            _ => None,
+        }).collect();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn query_finds_overlapping_circles_only() {
+        let mut hash = spatial_hash_new(10.0);
+        spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(1)), Vec2::new(0.0, 0.0), 2.0);
+        spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(2)), Vec2::new(15.0, 0.0), 2.0);
+        spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(3)), Vec2::new(100.0, 0.0), 2.0);
+
+        let found = spatial_hash_query(&hash, Vec2::new(8.0, 0.0), 6.5);
+        assert_eq!(food_items(&found), vec![Entity::from_raw(1), Entity::from_raw(2)]);
+        let found = spatial_hash_query(&hash, Vec2::new(8.0, 0.0), 4.0);
+        assert!(found.is_empty());
+    }
+
+    #[test]
+    fn big_circle_is_found_once() {
+        let mut hash = spatial_hash_new(10.0);
+        spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(1)), Vec2::new(0.0, 0.0), 35.0);
+        let found = spatial_hash_query(&hash, Vec2::new(20.0, 20.0), 30.0);
+        assert_eq!(found.len(), 1);
+    }
+
+    #[test]
+    fn negative_positions() {
+        let mut hash = spatial_hash_new(10.0);
+        spatial_hash_insert(&mut hash, SpatialItem::SnakeNode { snake: Entity::from_raw(1), node_index: 2 }, Vec2::new(-25.0, -5.0), 1.0);
+        let found = spatial_hash_query(&hash, Vec2::new(-22.0, -5.0), 3.0);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].item, SpatialItem::SnakeNode { snake: Entity::from_raw(1), node_index: 2 });
+    }
+
+    #[test]
+    fn clear_removes_entries() {
+        let mut hash = spatial_hash_new(10.0);
+        spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(1)), Vec2::ZERO, 1.0);
+        spatial_hash_clear(&mut hash);
+        assert!(spatial_hash_query(&hash, Vec2::ZERO, 5.0).is_empty());
+    }
+
+    #[test]
+    fn query_matches_brute_force() {
+        let mut rng = rand::thread_rng();
+        let mut hash = spatial_hash_new(50.0);
+        let mut circles: Vec<(Vec2, f32)> = Vec::new();
+        for i in 0..500 {
+            let pos = Vec2::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0));
+            let radius = rng.gen_range(1.0..30.0);
+            spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(i)), pos, radius);
+            circles.push((pos, radius));
+        }
+        for _ in 0..50 {
+            let pos = Vec2::new(rng.gen_range(-500.0..500.0), rng.gen_range(-500.0..500.0));
+            let radius = rng.gen_range(1.0..80.0);
+            let expected: Vec<Entity> = circles.iter().enumerate()
+                .filter(|(_, (circle_pos, circle_radius))| circle_pos.distance(pos) < circle_radius + radius)
+                .map(|(i, _)| Entity::from_raw(i as u32))
+                .collect();
+            assert_eq!(food_items(&spatial_hash_query(&hash, pos, radius)), expected);
+        }
+    }
+
+    /// Run with `cargo test benchmark -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn benchmark_thousands_of_food() {
+        let mut rng = rand::thread_rng();
+        let food: Vec<Vec2> = (0..10000).map(|_| Vec2::new(rng.gen_range(-5000.0..5000.0), rng.gen_range(-5000.0..5000.0))).collect();
+        let heads: Vec<Vec2> = (0..100).map(|_| Vec2::new(rng.gen_range(-5000.0..5000.0), rng.gen_range(-5000.0..5000.0))).collect();
+        let frames = 100;
+
+        let start = Instant::now();
+        let mut found_hash = 0;
+        let mut hash = spatial_hash_new(SPATIAL_HASH_CELL_SIZE);
+        for _ in 0..frames {
+            spatial_hash_clear(&mut hash);
+            for (i, pos) in food.iter().enumerate() {
+                spatial_hash_insert(&mut hash, SpatialItem::Food(Entity::from_raw(i as u32)), *pos, 10.0);
+            }
+            for head in heads.iter() {
+                found_hash += spatial_hash_query(&hash, *head, 50.0).len();
+            }
+        }
+        let hash_time = start.elapsed();
+
+        let start = Instant::now();
+        let mut found_brute_force = 0;
+        for _ in 0..frames {
+            for head in heads.iter() {
+                found_brute_force += food.iter().filter(|pos| pos.distance(*head) < 60.0).count();
+            }
+        }
+        let brute_force_time = start.elapsed();
+
+        println!("{} food, {} snakes, {} frames", food.len(), heads.len(), frames);
+        println!("spatial hash rebuild and query: {:?}", hash_time);
+        println!("brute force query: {:?}", brute_force_time);
+        assert_eq!(found_hash, found_brute_force);
+    }
+}
@@ -1,5 +1,7 @@
 use bevy::{color::palettes::basic::*, ecs::system::EntityCommands, gizmos, math::VectorSpace, prelude::*};
 
+use crate::highscore::*;
+
 pub struct StartPlugin;
 
 /// Global game component.
@@ -11,6 +13,9 @@ pub struct TheGame {
     /// If Some then point to Start button entity id. 
     /// If None then geme is running.
     pub start_button_entity: Option<Entity>,
+
+    /// Top scores shown under the Start button, removed together with it.
+    pub high_scores_entity: Option<Entity>,
 }
 
 
@@ -25,13 +30,15 @@ const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
 const PRESSED_BUTTON: Color = Color::srgb(0.35, 0.75, 0.35);
 
-fn create_game(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn create_game(mut commands: Commands, asset_server: Res<AssetServer>, high_scores: Res<HighScores>) {
 
     // All UI must be under this root node component.
     let mut node_bundle_entity_commands: EntityCommands<'_> = commands.spawn(NodeBundle {
         style: Style {
             width: Val::Percent(100.0),
             height: Val::Percent(100.0),
+            // start menu items go one under another
+            flex_direction: FlexDirection::Column,
             align_items: AlignItems::Center,
             justify_content: JustifyContent::Center,
             ..default()
@@ -41,12 +48,31 @@ fn create_game(mut commands: Commands, asset_server: Res<AssetServer>) {
 
     let mut the_game = TheGame { 
         root_ui_node: node_bundle_entity_commands.id(),
-        start_button_entity: None 
+        start_button_entity: None,
+        high_scores_entity: None,
     };
     create_start_button(&mut the_game, &mut node_bundle_entity_commands, &asset_server);
+    create_high_scores_table(&mut the_game, &mut node_bundle_entity_commands, &asset_server, &high_scores);
     commands.spawn(the_game);
 }
 
+fn create_high_scores_table(the_game: &mut TheGame, node_bundle_entity_command: &mut EntityCommands<'_>, asset_server: &Res<AssetServer>, high_scores: &HighScores) {
+    node_bundle_entity_command.with_children(|parent| {
+        let table = parent.spawn(TextBundle::from_section(
+            high_scores_text(&high_scores.entries),
+            TextStyle {
+                font: asset_server.load("MovistarTextRegular.ttf"),
+                font_size: 28.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+            },
+        ).with_text_justify(JustifyText::Center).with_style(Style {
+            margin: UiRect::top(Val::Px(20.0)),
+            ..default()
+        }));
+        the_game.high_scores_entity = Some(table.id());
+    });
+}
+
 fn create_start_button(the_game: &mut TheGame, node_bundle_entity_command: &mut EntityCommands<'_>, asset_server: &Res<AssetServer>) {
     node_bundle_entity_command.with_children(|parent| {
         let mut start_button_bundle_entity = parent.spawn(ButtonBundle {
@@ -100,6 +126,10 @@ fn despawn_start_button(
             the_game.start_button_entity = None;
         }
     }
+    if let Some(high_scores_entity) = the_game.high_scores_entity.take() {
+        commands.entity(the_game.root_ui_node).remove_children(&[ high_scores_entity ]);
+        commands.entity(high_scores_entity).despawn_recursive();
+    }
 }
 
 pub fn button_system(